@@ -35,9 +35,10 @@ if [ ! -d "$SHORTCUTS_SRC" ]; then
   echo "Fix install.sh SHORTCUTS_SRC to match your repo folder."
   exit 1
 fi
-mkdir -p "$DATA_DIR"
-rm -rf "$SHORTCUTS_DST"
-cp -a "$SHORTCUTS_SRC" "$SHORTCUTS_DST"
+mkdir -p "$SHORTCUTS_DST"
+# Replace bundled packs but keep user overlays (<app>.user.json)
+find "$SHORTCUTS_DST" -maxdepth 1 -name '*.json' ! -name '*.user.json' -delete
+cp -a "$SHORTCUTS_SRC/." "$SHORTCUTS_DST/"
 
 echo "[orbitkeys] installing desktop entry -> $DESKTOP_DST"
 if [ ! -f "$DESKTOP_SRC" ]; then
@@ -0,0 +1,179 @@
+//! OrbitKeys' own key bindings. They live in the bundled `orbitkeys` pack,
+//! each tied to an action by its `command`, so an overlay rebinds them like
+//! any app's shortcuts. The bundled file is compiled in for when no pack is
+//! installed.
+
+use crate::key_format::{Key, KeyChord, KeySequence, Modifiers};
+use crate::key_glyphs::KeyGlyph;
+use crate::shortcut_pack::ShortcutPack;
+use crate::shortcut_resolver::ShortcutResolver;
+
+#[cfg(test)]
+mod tests;
+
+/// The pack listing the bindings.
+pub const APP_KEYS_APP_ID: &str = "orbitkeys";
+
+const BUILT_IN: &str = include_str!("../shortcuts/orbitkeys.json");
+
+/// Something a key press does in the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppAction {
+    FocusSearch,
+    /// Close settings, else clear the search, else the selection.
+    ClearSearch,
+    PreviousEntry,
+    NextEntry,
+    PreviousCategory,
+    NextCategory,
+    Home,
+    Settings,
+    Quit,
+}
+
+impl AppAction {
+    /// The action for a pack entry's `command`.
+    fn from_command(command: &str) -> Option<Self> {
+        Some(match command.strip_prefix("orbitkeys.")? {
+            "focus-search" => AppAction::FocusSearch,
+            "clear-search" => AppAction::ClearSearch,
+            "previous-entry" => AppAction::PreviousEntry,
+            "next-entry" => AppAction::NextEntry,
+            "previous-category" => AppAction::PreviousCategory,
+            "next-category" => AppAction::NextCategory,
+            "home" => AppAction::Home,
+            "settings" => AppAction::Settings,
+            "quit" => AppAction::Quit,
+            _ => return None,
+        })
+    }
+}
+
+/// Chords bound to actions. Only single chords; sequences are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct AppKeys {
+    bindings: Vec<(KeyChord, AppAction)>,
+}
+
+impl AppKeys {
+    /// From the resolved `orbitkeys` pack, overlays included, or the
+    /// built-in one when it isn't installed or binds nothing.
+    pub fn load(resolver: &ShortcutResolver) -> Self {
+        let keys = match resolver.load(APP_KEYS_APP_ID) {
+            Ok(Some(pack)) => Self::from_entries(
+                pack.entries
+                    .iter()
+                    .map(|e| (e.keys.as_str(), e.command.as_deref())),
+            ),
+            _ => Self::default(),
+        };
+        match keys.bindings.is_empty() {
+            true => Self::built_in(),
+            false => keys,
+        }
+    }
+
+    /// The bindings of the bundled pack.
+    pub fn built_in() -> Self {
+        let pack = ShortcutPack::parse(BUILT_IN).unwrap_or_default();
+        Self::from_entries(
+            pack.shortcuts
+                .iter()
+                .map(|s| (s.keys.as_str(), s.command.as_deref())),
+        )
+    }
+
+    fn from_entries<'a>(entries: impl Iterator<Item = (&'a str, Option<&'a str>)>) -> Self {
+        let bindings = entries
+            .filter_map(|(keys, command)| {
+                let action = AppAction::from_command(command?)?;
+                match KeySequence::parse(keys).ok()?.0.as_slice() {
+                    [chord] => Some((chord.clone(), action)),
+                    _ => None,
+                }
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// What pressing `chord` does, if anything.
+    pub fn action(&self, chord: &KeyChord) -> Option<AppAction> {
+        self.bindings
+            .iter()
+            .find(|(c, _)| c == chord)
+            .map(|(_, action)| *action)
+    }
+}
+
+/// A key press as a chord, spelled like the packs spell keys. `key` is a key
+/// name ("Esc", "PageUp") or the character typed. Shift is dropped for
+/// symbols, since it picks the symbol: "?" not "Shift+/".
+pub fn pressed_chord(key: &str, held: Modifiers) -> Option<KeyChord> {
+    let symbol = key.chars().count() == 1 && !key.chars().all(char::is_alphabetic);
+
+    let mut raw = String::new();
+    for (held, label) in [
+        (held.super_key, "Super+"),
+        (held.ctrl, "Ctrl+"),
+        (held.alt, "Alt+"),
+        (held.shift && !symbol, "Shift+"),
+    ] {
+        if held {
+            raw.push_str(label);
+        }
+    }
+    raw.push_str(key);
+    KeyChord::parse(&raw).ok()
+}
+
+/// Whether a focused text input takes `chord` for itself.
+pub fn edits_text(chord: &KeyChord) -> bool {
+    let m = chord.modifiers;
+    if m.ctrl || m.alt || m.super_key {
+        return false;
+    }
+    match &chord.key {
+        Key::Char(_) => true,
+        Key::Named(g) => matches!(
+            g,
+            KeyGlyph::Space
+                | KeyGlyph::Left
+                | KeyGlyph::Right
+                | KeyGlyph::Home
+                | KeyGlyph::End
+                | KeyGlyph::Backspace
+                | KeyGlyph::Delete
+                | KeyGlyph::Plus
+                | KeyGlyph::Minus
+        ),
+        _ => false,
+    }
+}
+
+/// The selection after moving `step` entries from `selected`, or to the
+/// first entry of the category `step` away when `by_category`. `categories`
+/// has one item per entry, in grid order. Starts at either end and stops
+/// there rather than wrapping.
+pub fn move_selection(
+    categories: &[&str],
+    selected: Option<usize>,
+    step: isize,
+    by_category: bool,
+) -> Option<usize> {
+    let last = categories.len().checked_sub(1)?;
+
+    let Some(current) = selected.filter(|&i| i <= last) else {
+        return Some(if step < 0 { last } else { 0 });
+    };
+    if !by_category {
+        return Some(current.saturating_add_signed(step).min(last));
+    }
+
+    // the first entry of each category
+    let starts: Vec<usize> = (0..categories.len())
+        .filter(|&i| i == 0 || categories[i] != categories[i - 1])
+        .collect();
+    let here = starts.iter().rposition(|&s| s <= current).unwrap_or(0);
+    let target = here.saturating_add_signed(step).min(starts.len() - 1);
+    Some(starts[target])
+}
@@ -0,0 +1,96 @@
+use super::{edits_text, move_selection, pressed_chord, AppAction, AppKeys};
+use crate::key_format::{KeyChord, Modifiers};
+
+const NONE: Modifiers = Modifiers {
+    super_key: false,
+    ctrl: false,
+    alt: false,
+    altgr: false,
+    shift: false,
+};
+const SHIFT: Modifiers = Modifiers {
+    shift: true,
+    ..NONE
+};
+const CTRL_SHIFT: Modifiers = Modifiers {
+    ctrl: true,
+    shift: true,
+    ..NONE
+};
+
+fn chord(raw: &str) -> KeyChord {
+    KeyChord::parse(raw).unwrap()
+}
+
+#[test]
+fn shift_is_dropped_for_symbols_only() {
+    assert_eq!(pressed_chord("?", SHIFT), Some(chord("?")));
+    assert_eq!(pressed_chord("?", CTRL_SHIFT), Some(chord("Ctrl+?")));
+    assert_eq!(pressed_chord("+", CTRL_SHIFT), Some(chord("Ctrl++")));
+    assert_eq!(pressed_chord("A", SHIFT), Some(chord("Shift+A")));
+    assert_eq!(pressed_chord("p", CTRL_SHIFT), Some(chord("Ctrl+Shift+P")));
+    // named keys keep it too
+    assert_eq!(pressed_chord("Tab", SHIFT), Some(chord("Shift+Tab")));
+
+    assert_eq!(pressed_chord("Esc", NONE), Some(chord("Escape")));
+    // dead keys and the like
+    assert_eq!(pressed_chord("", NONE), None);
+}
+
+#[test]
+fn a_focused_search_keeps_what_it_types() {
+    let keys = AppKeys::built_in();
+    // what on_key_pressed acts on while the search field has focus
+    let captured = |raw: &str| {
+        let c = chord(raw);
+        (!edits_text(&c)).then(|| keys.action(&c)).flatten()
+    };
+
+    assert_eq!(captured("/"), None);
+    assert_eq!(captured("Shift+A"), None);
+    assert_eq!(captured("Left"), None);
+    assert_eq!(captured("Ctrl+F"), Some(AppAction::FocusSearch));
+    assert_eq!(captured("Esc"), Some(AppAction::ClearSearch));
+    assert_eq!(captured("Down"), Some(AppAction::NextEntry));
+
+    // unfocused, the same keys navigate
+    assert_eq!(keys.action(&chord("/")), Some(AppAction::FocusSearch));
+    assert_eq!(
+        keys.action(&chord("Left")),
+        Some(AppAction::PreviousCategory)
+    );
+}
+
+#[test]
+fn selection_starts_at_either_end() {
+    let cats = ["Files", "Files", "Edit"];
+    assert_eq!(move_selection(&cats, None, 1, false), Some(0));
+    assert_eq!(move_selection(&cats, None, -1, false), Some(2));
+    assert_eq!(move_selection(&cats, None, -1, true), Some(2));
+    // a selection past the end, after the list shrank
+    assert_eq!(move_selection(&cats, Some(7), 1, false), Some(0));
+
+    assert_eq!(move_selection(&[], Some(0), 1, false), None);
+}
+
+#[test]
+fn selection_stops_at_the_ends() {
+    let cats = ["Files", "Files", "Edit"];
+    assert_eq!(move_selection(&cats, Some(1), 1, false), Some(2));
+    assert_eq!(move_selection(&cats, Some(2), 1, false), Some(2));
+    assert_eq!(move_selection(&cats, Some(0), -1, false), Some(0));
+    assert_eq!(move_selection(&cats, Some(2), 1, true), Some(2));
+    assert_eq!(move_selection(&cats, Some(1), -1, true), Some(0));
+}
+
+#[test]
+fn selection_moves_by_category() {
+    let cats = ["Files", "Files", "Edit", "Edit", "Edit", "View"];
+    assert_eq!(move_selection(&cats, Some(1), 1, true), Some(2));
+    assert_eq!(move_selection(&cats, Some(3), 1, true), Some(5));
+    assert_eq!(move_selection(&cats, Some(4), -1, true), Some(0));
+    assert_eq!(move_selection(&cats, Some(5), -2, true), Some(0));
+    // two columns may both show a category; each run counts
+    let split = ["Files", "Edit", "Files"];
+    assert_eq!(move_selection(&split, Some(1), 1, true), Some(2));
+}
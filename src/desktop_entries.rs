@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests;
+
+/// The bits of an installed `.desktop` file the resolver uses for lookups.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DesktopEntry {
+    /// Desktop file ID, e.g. "org.mozilla.firefox" for `org.mozilla.firefox.desktop`.
+    pub id: String,
+    pub name: Option<String>,
+    pub startup_wm_class: Option<String>,
+    /// Program name from `Exec`; for Flatpak launchers, the `--command=` value.
+    pub exec_binary: Option<String>,
+    /// `X-Flatpak` application ID.
+    pub flatpak_id: Option<String>,
+}
+
+/// `$XDG_DATA_HOME/applications`, `$XDG_DATA_DIRS/*/applications` and the
+/// Flatpak export dirs (in case XDG_DATA_DIRS lacks them), most important first.
+pub fn application_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|h| PathBuf::from(h).join(".local/share")));
+    if let Some(d) = &data_home {
+        dirs.push(d.join("applications"));
+    }
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(std::env::split_paths(&data_dirs).map(|d| d.join("applications")));
+
+    let flatpak = [
+        data_home.map(|d| d.join("flatpak/exports/share/applications")),
+        Some(PathBuf::from("/var/lib/flatpak/exports/share/applications")),
+    ];
+    for dir in flatpak.into_iter().flatten() {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    dirs
+}
+
+/// Read every desktop file under `dirs`. Earlier dirs shadow later ones with the same ID.
+pub fn scan(dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut out: Vec<DesktopEntry> = Vec::new();
+
+    for dir in dirs {
+        let mut found = Vec::new();
+        collect(dir, dir, &mut found);
+        found.sort();
+
+        for (id, path) in found {
+            if out.iter().any(|e| e.id == id) {
+                continue;
+            }
+            if let Some(entry) = parse(&id, &path) {
+                out.push(entry);
+            }
+        }
+    }
+
+    out
+}
+
+/// Desktop IDs for files in subdirectories join the path with '-'
+/// (`kde/foo.desktop` -> `kde-foo`).
+fn collect(base: &Path, dir: &Path, out: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(base, &path, out);
+            continue;
+        }
+        if path.extension().and_then(|s| s.to_str()) != Some("desktop") {
+            continue;
+        }
+
+        let Ok(rel) = path.strip_prefix(base) else {
+            continue;
+        };
+        let id = rel
+            .with_extension("")
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "-");
+        out.push((id, path));
+    }
+}
+
+fn parse(id: &str, path: &Path) -> Option<DesktopEntry> {
+    let raw = fs::read_to_string(path).ok()?;
+
+    let mut in_main = false;
+    let mut entry = DesktopEntry {
+        id: id.to_string(),
+        ..Default::default()
+    };
+
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_main = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "Name" => entry.name = Some(value),
+            "StartupWMClass" => entry.startup_wm_class = Some(value),
+            "Exec" => entry.exec_binary = exec_binary(&value),
+            "X-Flatpak" => entry.flatpak_id = Some(value),
+            _ => {}
+        }
+    }
+
+    Some(entry)
+}
+
+/// Program name from an `Exec` line: skips `env VAR=…` and takes the basename.
+/// `flatpak run … --command=foo app.Id` yields "foo".
+fn exec_binary(exec: &str) -> Option<String> {
+    let words = exec_words(exec);
+    let mut words = words.iter().map(String::as_str);
+
+    let mut program = words.next()?;
+    if basename(program) == "env" {
+        program = words.by_ref().find(|w| !w.contains('=') && !w.starts_with('-'))?;
+    }
+
+    if basename(program) == "flatpak" {
+        return words
+            .find_map(|w| w.strip_prefix("--command="))
+            .map(|c| basename(c).to_string());
+    }
+
+    Some(basename(program).to_string())
+}
+
+/// The arguments of an `Exec` line. Double quotes keep spaces in one
+/// argument, with `\` escaping the next char inside them.
+fn exec_words(exec: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => word.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn basename(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use super::{exec_binary, scan, DesktopEntry};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/desktop_entries/fixtures")
+        .join(name)
+}
+
+fn entry(entries: &[DesktopEntry], id: &str) -> DesktopEntry {
+    entries.iter().find(|e| e.id == id).cloned().unwrap()
+}
+
+#[test]
+fn exec_skips_env_assignments() {
+    let exec = "env GDK_BACKEND=x11 MOZ_ENABLE_WAYLAND=1 /usr/bin/firefox %u";
+    assert_eq!(exec_binary(exec).as_deref(), Some("firefox"));
+}
+
+#[test]
+fn exec_keeps_quoted_paths_whole() {
+    let exec = r#""/opt/My App/bin/my-app" --name "a \"b\"" %U"#;
+    assert_eq!(exec_binary(exec).as_deref(), Some("my-app"));
+}
+
+#[test]
+fn exec_ignores_field_codes() {
+    assert_eq!(exec_binary("gimp-2.10 %U").as_deref(), Some("gimp-2.10"));
+    assert_eq!(
+        exec_binary("/usr/bin/vlc --started-from-file %F").as_deref(),
+        Some("vlc")
+    );
+    assert_eq!(exec_binary("   "), None);
+}
+
+#[test]
+fn exec_of_flatpak_launchers_is_the_command() {
+    let exec =
+        "/usr/bin/flatpak run --branch=stable --command=obsidian.sh md.obsidian.Obsidian @@u %U @@";
+    assert_eq!(exec_binary(exec).as_deref(), Some("obsidian.sh"));
+    assert_eq!(exec_binary("flatpak run org.gimp.GIMP"), None);
+}
+
+#[test]
+fn scan_reads_the_main_group() {
+    let entries = scan(&[fixture("applications")]);
+
+    assert_eq!(
+        entry(&entries, "org.example.Editor"),
+        DesktopEntry {
+            id: "org.example.Editor".into(),
+            name: Some("Example Editor".into()),
+            startup_wm_class: Some("example-editor".into()),
+            exec_binary: Some("example-editor".into()),
+            flatpak_id: None,
+        }
+    );
+
+    let obsidian = entry(&entries, "md.obsidian.Obsidian");
+    assert_eq!(obsidian.exec_binary.as_deref(), Some("obsidian.sh"));
+    assert_eq!(obsidian.flatpak_id.as_deref(), Some("md.obsidian.Obsidian"));
+}
+
+#[test]
+fn scan_names_subdirectory_files_by_path() {
+    let entries = scan(&[fixture("applications")]);
+    let viewer = entry(&entries, "kde-viewer");
+    assert_eq!(viewer.exec_binary.as_deref(), Some("viewer"));
+}
+
+#[test]
+fn earlier_dirs_shadow_later_ones() {
+    let entries = scan(&[fixture("local"), fixture("applications")]);
+    let editor = entry(&entries, "org.example.Editor");
+    assert_eq!(editor.name.as_deref(), Some("My Editor"));
+    assert_eq!(entries.iter().filter(|e| e.id == editor.id).count(), 1);
+}
@@ -1,243 +1,845 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::os::unix::fs::FileExt;
 use std::sync::Arc;
+use std::time::Duration;
 
-use wayland_client::backend::ObjectData;
-use wayland_client::protocol::wl_registry;
+use rustix::event::{poll, PollFd, PollFlags};
+use rustix::io::Errno;
+use wayland_client::backend::{ObjectData, WaylandError};
+use wayland_client::protocol::{wl_keyboard, wl_output, wl_registry, wl_seat};
 use wayland_client::{
-    globals::{registry_queue_init, GlobalListContents},
-    Connection, Dispatch, Proxy, QueueHandle,
+    globals::{registry_queue_init, GlobalList, GlobalListContents},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
 };
 
 // Protocol imports
-use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
-    ext_foreign_toplevel_handle_v1, ext_foreign_toplevel_list_v1,
-};
-use cosmic_protocols::toplevel_info::v1::client::{
-    zcosmic_toplevel_handle_v1, zcosmic_toplevel_info_v1,
+use cosmic_protocols::workspace::v1::client::{
+    zcosmic_workspace_group_handle_v1, zcosmic_workspace_handle_v1, zcosmic_workspace_manager_v1,
 };
 
-/// Run the focus watcher and call `on_focus(app_id)` whenever focus changes.
-/// This function blocks forever (Wayland dispatch loop).
-pub fn run_focus_watcher<F>(on_focus: F) -> anyhow::Result<()>
+use crate::shutdown::Shutdown;
+
+mod cosmic;
+mod ext_list;
+mod wlr;
+
+#[cfg(test)]
+mod test_compositor;
+#[cfg(test)]
+mod tests;
+
+use cosmic::Cosmic;
+use ext_list::ExtList;
+use wlr::Wlr;
+
+/// What happened to the window a [`FocusEvent`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusEventKind {
+    /// The window became the active one.
+    Activated,
+    /// The active window's title changed.
+    TitleChanged,
+    /// A window (focused or not) was closed.
+    Closed,
+    /// No window is active anymore; carries the window that last had focus.
+    FocusLost,
+}
+
+/// A window as the compositor last described it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusEvent {
+    pub kind: FocusEventKind,
+    pub app_id: String,
+    pub title: String,
+    /// ext-foreign-toplevel identifier; stable for the window's lifetime and
+    /// unique even between windows of the same app. The wlr backend makes one
+    /// up from its handle.
+    pub identifier: String,
+    pub maximized: bool,
+    pub minimized: bool,
+    pub fullscreen: bool,
+    /// `wl_output` name (e.g. "DP-1") of the first output the window is on.
+    pub output: Option<String>,
+    /// Name of the workspace the window is on.
+    pub workspace: Option<String>,
+}
+
+/// The active workspace, sent when it or its layout changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceEvent {
+    pub name: Option<String>,
+    /// Whether tiling is enabled. `None` when the compositor doesn't say
+    /// (cosmic-workspace v1).
+    pub tiling: Option<bool>,
+}
+
+/// Where the watcher learns about windows from, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// ext-foreign-toplevel-list plus `zcosmic_toplevel_info_v1` for state.
+    Cosmic,
+    /// `zwlr_foreign_toplevel_manager_v1`, as on wlroots compositors.
+    Wlr,
+    /// ext-foreign-toplevel-list alone. It has no activated state, so the
+    /// newest window is assumed to have focus.
+    ExtList,
+}
+
+impl Backend {
+    pub fn label(self) -> &'static str {
+        match self {
+            Backend::Cosmic => "COSMIC toplevel info",
+            Backend::Wlr => "wlr-foreign-toplevel-management",
+            Backend::ExtList => "ext-foreign-toplevel-list",
+        }
+    }
+
+    /// Whether it reports the active window rather than guessing.
+    pub fn tracks_focus(self) -> bool {
+        self != Backend::ExtList
+    }
+}
+
+/// What the watcher reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatcherEvent {
+    /// Sent first. `None` when the compositor offers none of the supported
+    /// protocols; the watcher returns right after.
+    Backend(Option<Backend>),
+    Focus(FocusEvent),
+    Workspace(WorkspaceEvent),
+    /// The keyboard's keymap in the xkb text format, sent when it is first
+    /// known and whenever the layout changes.
+    Keymap(String),
+    /// The keymap's active layout (xkb group), when it changes. Switching
+    /// between the layouts of one keymap only sends this.
+    KeyboardGroup(u32),
+    /// Connecting failed or the connection dropped (e.g. the compositor
+    /// restarted). The watcher tries again after `retry_in` and starts over
+    /// with a `Backend` event.
+    Disconnected {
+        error: String,
+        retry_in: Duration,
+    },
+}
+
+/// Delay between reconnect attempts: doubles from `initial` up to `max`, and
+/// starts over once a connection is up.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Run the focus watcher and call `on_event(event)` whenever focus or the
+/// active workspace changes. Blocks in the Wayland dispatch loop until
+/// `shutdown` is stopped, reconnecting when the connection drops. Returns
+/// early only if the compositor has no supported protocol.
+pub fn run_focus_watcher<F>(shutdown: Shutdown, on_event: F)
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Clone + Send + 'static,
 {
-    let conn = Connection::connect_to_env()?;
-    let (globals, mut queue) = registry_queue_init::<DiscoveryState<F>>(&conn)?;
-    let qh = queue.handle();
+    let connect = || Ok(Connection::connect_to_env()?);
+    watch(&shutdown, Backoff::default(), connect, on_event);
+}
 
-    let cosmic_info = globals.bind::<zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1, _, _>(
-        &qh,
-        1..=3,
-        (),
-    )?;
-    let _list = globals.bind::<ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1, _, _>(
-        &qh,
-        1..=1,
-        (),
-    )?;
+/// `run_focus_watcher` with the connection and delays supplied. Each connection
+/// gets its own copy of `on_event`.
+pub(crate) fn watch<C, F>(shutdown: &Shutdown, backoff: Backoff, mut connect: C, mut on_event: F)
+where
+    C: FnMut() -> anyhow::Result<Connection>,
+    F: FnMut(WatcherEvent) + Clone + Send + 'static,
+{
+    let mut retry_in = backoff.initial;
+
+    while !shutdown.is_stopped() {
+        let session = connect().and_then(|conn| {
+            let Some(mut watcher) = FocusWatcher::new(&conn, on_event.clone())? else {
+                return Ok(());
+            };
+            retry_in = backoff.initial;
+
+            while watcher.dispatch(shutdown)? {}
+            Ok(())
+        });
+        let Err(e) = session else {
+            return;
+        };
+
+        on_event(WatcherEvent::Disconnected {
+            error: format!("{e:#}"),
+            retry_in,
+        });
+        if shutdown.wait_timeout(retry_in) {
+            return;
+        }
+        retry_in = (retry_in * 2).min(backoff.max);
+    }
+}
 
-    let mut state = DiscoveryState {
-        // Keep handles alive
-        cosmic_handles: HashMap::new(),
-        ext_handles: HashMap::new(),
+/// A protocol family that reports windows and their state. Each backend binds
+/// its globals on the watcher's queue, and its Dispatch impls feed the
+/// protocol-independent `DiscoveryState` methods.
+trait FocusBackend: Sized + 'static {
+    const KIND: Backend;
 
-        // ext handle id -> app_id
-        ext_app_ids: HashMap::new(),
+    /// Bind the backend's globals, or `None` if the compositor lacks them.
+    fn bind<F>(globals: &GlobalList, qh: &QueueHandle<DiscoveryState<F, Self>>) -> Option<Self>
+    where
+        F: FnMut(WatcherEvent) + Send + 'static;
+}
 
-        // cosmic handle id -> ext handle id
-        cosmic_to_ext: HashMap::new(),
+/// Bound globals plus the event queue, on any connection. `run_focus_watcher`
+/// drives it against the session compositor; tests drive it against a mock.
+pub(crate) struct FocusWatcher {
+    // the backend is picked at runtime, so its type is erased
+    session: Box<dyn Session>,
+}
 
-        cosmic_info: Some(cosmic_info),
+impl FocusWatcher {
+    /// Bind the first backend the compositor supports and report which one
+    /// through `on_event`. `None` if there is none.
+    pub(crate) fn new<F>(conn: &Connection, on_event: F) -> anyhow::Result<Option<Self>>
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        let on_event = match Self::start::<F, Cosmic>(conn, on_event)? {
+            Ok(watcher) => return Ok(Some(watcher)),
+            Err(on_event) => on_event,
+        };
+        let on_event = match Self::start::<F, Wlr>(conn, on_event)? {
+            Ok(watcher) => return Ok(Some(watcher)),
+            Err(on_event) => on_event,
+        };
+        let mut on_event = match Self::start::<F, ExtList>(conn, on_event)? {
+            Ok(watcher) => return Ok(Some(watcher)),
+            Err(on_event) => on_event,
+        };
+
+        on_event(WatcherEvent::Backend(None));
+        Ok(None)
+    }
+
+    /// Try backend `B`, handing `on_event` back if its globals are missing.
+    fn start<F, B>(conn: &Connection, mut on_event: F) -> anyhow::Result<Result<Self, F>>
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+        B: FocusBackend,
+    {
+        let (globals, queue) = registry_queue_init::<DiscoveryState<F, B>>(conn)?;
+        let qh = queue.handle();
+
+        let Some(backend) = B::bind(&globals, &qh) else {
+            return Ok(Err(on_event));
+        };
+        on_event(WatcherEvent::Backend(Some(B::KIND)));
+
+        let mut state = DiscoveryState::new(backend, on_event);
+
+        // Optional: without these, events just carry no output/workspace names.
+        state.workspace_manager = globals
+            .bind::<zcosmic_workspace_manager_v1::ZcosmicWorkspaceManagerV1, _, _>(&qh, 1..=2, ())
+            .ok();
+        // Optional too: only for the keymap.
+        state.seat = globals.bind::<wl_seat::WlSeat, _, _>(&qh, 1..=7, ()).ok();
+        for g in globals.contents().clone_list() {
+            if g.interface == wl_output::WlOutput::interface().name {
+                state.bind_output(globals.registry(), g.name, g.version, &qh);
+            }
+        }
 
-        // debounce (avoid spamming same app)
-        last_focused: None,
+        Ok(Ok(Self {
+            session: Box::new(Queue { queue, state }),
+        }))
+    }
 
-        // callback
-        on_focus,
-    };
+    /// Block until events arrive and dispatch them. Returns false, without
+    /// reading further, once `shutdown` is stopped.
+    pub(crate) fn dispatch(&mut self, shutdown: &Shutdown) -> anyhow::Result<bool> {
+        self.session.dispatch(shutdown)
+    }
 
-    loop {
-        queue.blocking_dispatch(&mut state)?;
+    /// Dispatch until the compositor has processed everything we sent.
+    #[cfg(test)]
+    pub(crate) fn roundtrip(&mut self) -> anyhow::Result<()> {
+        self.session.roundtrip()
     }
 }
 
-struct DiscoveryState<F>
+/// `FocusWatcher`'s queue and state, whatever the backend.
+trait Session {
+    fn dispatch(&mut self, shutdown: &Shutdown) -> anyhow::Result<bool>;
+
+    #[cfg(test)]
+    fn roundtrip(&mut self) -> anyhow::Result<()>;
+}
+
+struct Queue<F, B>
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
 {
-    // Keep handles alive
-    cosmic_handles: HashMap<u32, zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1>,
-    ext_handles: HashMap<u32, ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1>,
+    queue: EventQueue<DiscoveryState<F, B>>,
+    state: DiscoveryState<F, B>,
+}
 
-    // ext handle id -> app_id
-    ext_app_ids: HashMap<u32, String>,
+impl<F, B> Session for Queue<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
+{
+    fn dispatch(&mut self, shutdown: &Shutdown) -> anyhow::Result<bool> {
+        if shutdown.is_stopped() {
+            return Ok(false);
+        }
 
-    // cosmic handle id -> ext handle id
-    cosmic_to_ext: HashMap<u32, u32>,
+        // Same steps as `blocking_dispatch`, but polling the shutdown fd as well.
+        if self.queue.dispatch_pending(&mut self.state)? > 0 {
+            self.state.settle();
+            return Ok(true);
+        }
+        self.queue.flush()?;
+
+        if let Some(guard) = self.queue.prepare_read() {
+            let conn_fd = guard.connection_fd();
+            let stop_fd = shutdown.fd();
+            let mut fds = [
+                PollFd::new(&conn_fd, PollFlags::IN | PollFlags::ERR),
+                PollFd::new(&stop_fd, PollFlags::IN),
+            ];
+
+            loop {
+                match poll(&mut fds, None) {
+                    Ok(_) => break,
+                    Err(Errno::INTR) => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            // Dropping the guard cancels the read.
+            if !fds[1].revents().is_empty() {
+                return Ok(false);
+            }
+
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        self.queue.dispatch_pending(&mut self.state)?;
+        self.state.settle();
+        Ok(true)
+    }
+
+    #[cfg(test)]
+    fn roundtrip(&mut self) -> anyhow::Result<()> {
+        self.queue.roundtrip(&mut self.state)?;
+        self.state.settle();
+        Ok(())
+    }
+}
+
+/// COSMIC shell overlays (launcher, app library, workspaces overview). They are
+/// normally layer surfaces, which take keyboard focus without being toplevels,
+/// so the window underneath simply loses `activated`. When one does show up as
+/// a toplevel it counts as "no window focused" too.
+const SHELL_APP_IDS: &[&str] = &[
+    "com.system76.CosmicLauncher",
+    "com.system76.CosmicAppLibrary",
+    "com.system76.CosmicWorkspaces",
+];
+
+fn is_shell_surface(app_id: &str) -> bool {
+    SHELL_APP_IDS.contains(&app_id)
+}
+
+/// Values of a protocol `state` array: a list of native-endian u32s.
+fn state_values(raw: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    raw.chunks_exact(4)
+        .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+}
+
+/// Everything known about one toplevel, keyed by the backend's handle id.
+#[derive(Default)]
+struct Window {
+    app_id: String,
+    title: String,
+    identifier: String,
+
+    activated: bool,
+    maximized: bool,
+    minimized: bool,
+    fullscreen: bool,
+
+    // protocol ids of the wl_outputs / workspace handles it is on
+    outputs: Vec<u32>,
+    workspaces: Vec<u32>,
+}
+
+struct Output {
+    global: u32,
+    proxy: wl_output::WlOutput,
+    name: Option<String>,
+}
+
+struct Workspace {
+    handle: zcosmic_workspace_handle_v1::ZcosmicWorkspaceHandleV1,
+    name: Option<String>,
+    active: bool,
+    tiling: Option<bool>,
+}
 
-    cosmic_info: Option<zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1>,
+struct DiscoveryState<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+{
+    // protocol-specific handles and bookkeeping
+    backend: B,
+
+    // window id -> window
+    windows: HashMap<u32, Window>,
+
+    outputs: Vec<Output>,
+
+    workspace_manager: Option<zcosmic_workspace_manager_v1::ZcosmicWorkspaceManagerV1>,
+    workspace_groups: Vec<zcosmic_workspace_group_handle_v1::ZcosmicWorkspaceGroupHandleV1>,
+    // workspace handle id -> workspace
+    workspaces: HashMap<u32, Workspace>,
+    // last WorkspaceEvent we sent
+    reported_workspace: Option<WorkspaceEvent>,
+
+    seat: Option<wl_seat::WlSeat>,
+    keyboard: Option<wl_keyboard::WlKeyboard>,
+    // last keymap and group we sent
+    reported_keymap: Option<String>,
+    reported_group: Option<u32>,
 
-    last_focused: Option<String>,
+    // window id of the active window
+    focused: Option<u32>,
+    // window and event of the last Activated / TitleChanged we sent
+    reported: Option<(u32, FocusEvent)>,
 
-    on_focus: F,
+    on_event: F,
 }
 
-// 1) ext-foreign-toplevel-list: new window discovered
-impl<F> Dispatch<ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1, ()> for DiscoveryState<F>
+// Protocol-independent bookkeeping; the Dispatch impls only translate Wayland
+// events into these calls.
+impl<F, B> DiscoveryState<F, B>
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
+{
+    fn new(backend: B, on_event: F) -> Self {
+        Self {
+            backend,
+            windows: HashMap::new(),
+            outputs: Vec::new(),
+            workspace_manager: None,
+            workspace_groups: Vec::new(),
+            workspaces: HashMap::new(),
+            reported_workspace: None,
+            seat: None,
+            keyboard: None,
+            reported_keymap: None,
+            reported_group: None,
+            focused: None,
+            reported: None,
+            on_event,
+        }
+    }
+
+    fn bind_output(
+        &mut self,
+        registry: &wl_registry::WlRegistry,
+        global: u32,
+        version: u32,
+        qh: &QueueHandle<Self>,
+    ) where
+        Self: Dispatch<wl_output::WlOutput, ()>,
+    {
+        // `name` arrived in v4
+        let proxy = registry.bind::<wl_output::WlOutput, _, _>(global, version.min(4), qh, ());
+        self.outputs.push(Output {
+            global,
+            proxy,
+            name: None,
+        });
+    }
+
+    fn event_for(&self, kind: FocusEventKind, id: u32) -> Option<FocusEvent> {
+        let w = self.windows.get(&id)?;
+
+        let output = w.outputs.iter().find_map(|id| {
+            self.outputs
+                .iter()
+                .find(|o| o.proxy.id().protocol_id() == *id)
+                .and_then(|o| o.name.clone())
+        });
+        let workspace = w
+            .workspaces
+            .iter()
+            .find_map(|id| self.workspaces.get(id).and_then(|ws| ws.name.clone()));
+
+        Some(FocusEvent {
+            kind,
+            app_id: w.app_id.clone(),
+            title: w.title.clone(),
+            identifier: w.identifier.clone(),
+            maximized: w.maximized,
+            minimized: w.minimized,
+            fullscreen: w.fullscreen,
+            output,
+            workspace,
+        })
+    }
+
+    fn emit(&mut self, kind: FocusEventKind, id: u32) {
+        if let Some(ev) = self.event_for(kind, id) {
+            if kind != FocusEventKind::Closed {
+                self.reported = Some((id, ev.clone()));
+            }
+            (self.on_event)(WatcherEvent::Focus(ev));
+        }
+    }
+
+    /// A batch of changes for one window is complete (`done`). Only records
+    /// which window is active; `settle` reports it.
+    fn window_done(&mut self, id: u32) {
+        let Some(w) = self.windows.get(&id) else { return };
+
+        if w.activated && !is_shell_surface(&w.app_id) {
+            self.focused = Some(id);
+        } else if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+
+    fn window_closed(&mut self, id: u32) {
+        self.emit(FocusEventKind::Closed, id);
+
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+        self.windows.remove(&id);
+    }
+
+    /// Report the net change of a dispatched batch, so that switching windows
+    /// doesn't pass through a "nothing focused" state.
+    fn settle(&mut self) {
+        self.settle_focus();
+        self.settle_workspace();
+    }
+
+    fn settle_focus(&mut self) {
+        let reported = self.reported.as_ref().map(|(id, _)| *id);
+
+        match (self.focused, reported) {
+            (Some(f), Some(r)) if f == r => {
+                let title = self.windows.get(&f).map(|w| w.title.as_str());
+                let seen = self.reported.as_ref().map(|(_, ev)| ev.title.as_str());
+                if title != seen {
+                    self.emit(FocusEventKind::TitleChanged, f);
+                }
+            }
+            (Some(f), _) => self.emit(FocusEventKind::Activated, f),
+            (None, Some(r)) => {
+                let Some((_, last)) = self.reported.take() else {
+                    return;
+                };
+                // Still mapped (just deactivated): describe it as it is now.
+                let ev = self
+                    .event_for(FocusEventKind::FocusLost, r)
+                    .unwrap_or(FocusEvent {
+                        kind: FocusEventKind::FocusLost,
+                        ..last
+                    });
+                (self.on_event)(WatcherEvent::Focus(ev));
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn settle_workspace(&mut self) {
+        let Some(ev) = self.active_workspace() else {
+            return;
+        };
+        if self.reported_workspace.as_ref() != Some(&ev) {
+            self.reported_workspace = Some(ev.clone());
+            (self.on_event)(WatcherEvent::Workspace(ev));
+        }
+    }
+
+    /// The focused window's workspace, or with nothing focused the first
+    /// active one (each output group has its own).
+    fn active_workspace(&self) -> Option<WorkspaceEvent> {
+        let is_active = |id: &u32| self.workspaces.get(id).is_some_and(|ws| ws.active);
+
+        let focused = self
+            .focused
+            .and_then(|f| self.windows.get(&f))
+            .and_then(|w| w.workspaces.iter().copied().find(is_active));
+        let id = focused.or_else(|| self.workspaces.keys().copied().filter(is_active).min())?;
+
+        let ws = &self.workspaces[&id];
+        Some(WorkspaceEvent {
+            name: ws.name.clone(),
+            tiling: ws.tiling,
+        })
+    }
+}
+
+// Outputs and workspaces, for their names. The toplevel protocols are handled
+// in the backend modules.
+impl<F, B> Dispatch<wl_output::WlOutput, ()> for DiscoveryState<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
 {
     fn event(
         state: &mut Self,
-        _: &ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1,
-        event: ext_foreign_toplevel_list_v1::Event,
+        proxy: &wl_output::WlOutput,
+        event: wl_output::Event,
         _: &(),
         _: &Connection,
-        qh: &QueueHandle<Self>,
+        _: &QueueHandle<Self>,
     ) {
-        if let ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } = event {
-            // Keep ext handle alive so we keep receiving its events (AppId, Title, etc.)
-            let ext_id = toplevel.id().protocol_id();
-            state.ext_handles.insert(ext_id, toplevel.clone());
-
-            // Create + keep cosmic handle alive for focus/activated state
-            if let Some(mgr) = &state.cosmic_info {
-                let cosmic_handle = mgr.get_cosmic_toplevel(&toplevel, qh, ());
-                let cosmic_id = cosmic_handle.id().protocol_id();
-
-                state.cosmic_to_ext.insert(cosmic_id, ext_id);
-                state.cosmic_handles.insert(cosmic_id, cosmic_handle);
+        if let wl_output::Event::Name { name } = event {
+            if let Some(o) = state.outputs.iter_mut().find(|o| &o.proxy == proxy) {
+                o.name = Some(name);
             }
         }
     }
+}
+
+impl<F, B> Dispatch<zcosmic_workspace_manager_v1::ZcosmicWorkspaceManagerV1, ()>
+    for DiscoveryState<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
+{
+    fn event(
+        state: &mut Self,
+        _: &zcosmic_workspace_manager_v1::ZcosmicWorkspaceManagerV1,
+        event: zcosmic_workspace_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zcosmic_workspace_manager_v1::Event::WorkspaceGroup { workspace_group } = event {
+            state.workspace_groups.push(workspace_group);
+        }
+    }
 
     fn event_created_child(_opcode: u16, qh: &QueueHandle<Self>) -> Arc<dyn ObjectData> {
-        qh.make_data::<ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1, ()>(())
+        qh.make_data::<zcosmic_workspace_group_handle_v1::ZcosmicWorkspaceGroupHandleV1, ()>(())
     }
 }
 
-// 2) ext handle events: capture AppId here
-impl<F> Dispatch<ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1, ()> for DiscoveryState<F>
+impl<F, B> Dispatch<zcosmic_workspace_group_handle_v1::ZcosmicWorkspaceGroupHandleV1, ()>
+    for DiscoveryState<F, B>
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
 {
     fn event(
         state: &mut Self,
-        proxy: &ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
-        event: ext_foreign_toplevel_handle_v1::Event,
+        proxy: &zcosmic_workspace_group_handle_v1::ZcosmicWorkspaceGroupHandleV1,
+        event: zcosmic_workspace_group_handle_v1::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        let ext_id = proxy.id().protocol_id();
-
         match event {
-            ext_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
-                state.ext_app_ids.insert(ext_id, app_id);
-            }
-
-            ext_foreign_toplevel_handle_v1::Event::Closed => {
-                state.ext_app_ids.remove(&ext_id);
-                state.ext_handles.remove(&ext_id);
-
-                // remove any cosmic handle mapped to this ext handle
-                if let Some((cosmic_id, _)) = state
-                    .cosmic_to_ext
-                    .iter()
-                    .find(|(_, v)| **v == ext_id)
-                    .map(|(k, v)| (*k, *v))
-                {
-                    state.cosmic_to_ext.remove(&cosmic_id);
-                    state.cosmic_handles.remove(&cosmic_id);
-                }
+            zcosmic_workspace_group_handle_v1::Event::Workspace { workspace } => {
+                state.workspaces.insert(
+                    workspace.id().protocol_id(),
+                    Workspace {
+                        handle: workspace,
+                        name: None,
+                        active: false,
+                        tiling: None,
+                    },
+                );
+            }
+            zcosmic_workspace_group_handle_v1::Event::Remove => {
+                state.workspace_groups.retain(|g| g != proxy);
             }
-
             _ => {}
         }
     }
+
+    fn event_created_child(_opcode: u16, qh: &QueueHandle<Self>) -> Arc<dyn ObjectData> {
+        qh.make_data::<zcosmic_workspace_handle_v1::ZcosmicWorkspaceHandleV1, ()>(())
+    }
 }
 
-// 3) COSMIC handle events: activated => focus changed
-impl<F> Dispatch<zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1, ()> for DiscoveryState<F>
+impl<F, B> Dispatch<zcosmic_workspace_handle_v1::ZcosmicWorkspaceHandleV1, ()>
+    for DiscoveryState<F, B>
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
 {
     fn event(
         state: &mut Self,
-        proxy: &zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1,
-        event: zcosmic_toplevel_handle_v1::Event,
+        proxy: &zcosmic_workspace_handle_v1::ZcosmicWorkspaceHandleV1,
+        event: zcosmic_workspace_handle_v1::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        let cosmic_id = proxy.id().protocol_id();
+        let id = proxy.id().protocol_id();
 
         match event {
-            zcosmic_toplevel_handle_v1::Event::State { state: raw_bytes } => {
-                let is_activated = raw_bytes
-                    .chunks_exact(4)
-                    .any(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]) == 2);
-
-                if !is_activated {
-                    return;
+            zcosmic_workspace_handle_v1::Event::Name { name } => {
+                if let Some(ws) = state.workspaces.get_mut(&id) {
+                    ws.name = Some(name);
                 }
-
-                let ext_id = state.cosmic_to_ext.get(&cosmic_id).copied();
-                let app = ext_id
-                    .and_then(|eid| state.ext_app_ids.get(&eid))
-                    .cloned()
-                    .unwrap_or_else(|| "unknown".to_string());
-
-                if state.last_focused.as_deref() == Some(&app) {
-                    return;
+            }
+            zcosmic_workspace_handle_v1::Event::State { state: raw } => {
+                let active = zcosmic_workspace_handle_v1::State::Active as u32;
+                if let Some(ws) = state.workspaces.get_mut(&id) {
+                    ws.active = state_values(&raw).any(|v| v == active);
                 }
-                state.last_focused = Some(app.clone());
-
-                (state.on_focus)(app);
             }
-
-            zcosmic_toplevel_handle_v1::Event::Closed => {
-                state.cosmic_handles.remove(&cosmic_id);
-                state.cosmic_to_ext.remove(&cosmic_id);
+            zcosmic_workspace_handle_v1::Event::TilingState { state: tiling } => {
+                use zcosmic_workspace_handle_v1::TilingState;
+
+                if let Some(ws) = state.workspaces.get_mut(&id) {
+                    ws.tiling = match tiling {
+                        WEnum::Value(TilingState::TilingEnabled) => Some(true),
+                        WEnum::Value(TilingState::FloatingOnly) => Some(false),
+                        _ => None,
+                    };
+                }
+            }
+            zcosmic_workspace_handle_v1::Event::Remove => {
+                if let Some(ws) = state.workspaces.remove(&id) {
+                    ws.handle.destroy();
+                }
             }
-
             _ => {}
         }
     }
 }
 
-// 4) Boilerplate
-impl<F> Dispatch<wl_registry::WlRegistry, GlobalListContents> for DiscoveryState<F>
+// The seat's keyboard, for its keymap and active group. Key events only go to
+// clients with a focused surface, which we aren't on this connection.
+impl<F, B> Dispatch<wl_seat::WlSeat, ()> for DiscoveryState<F, B>
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
 {
     fn event(
-        _: &mut Self,
-        _: &wl_registry::WlRegistry,
-        _: wl_registry::Event,
-        _: &GlobalListContents,
+        state: &mut Self,
+        seat: &wl_seat::WlSeat,
+        event: wl_seat::Event,
+        _: &(),
         _: &Connection,
-        _: &QueueHandle<Self>,
+        qh: &QueueHandle<Self>,
     ) {
+        let wl_seat::Event::Capabilities { capabilities } = event else {
+            return;
+        };
+        let has_keyboard = match capabilities {
+            WEnum::Value(caps) => caps.contains(wl_seat::Capability::Keyboard),
+            WEnum::Unknown(_) => false,
+        };
+
+        match (has_keyboard, state.keyboard.take()) {
+            (true, None) => state.keyboard = Some(seat.get_keyboard(qh, ())),
+            (true, keyboard) => state.keyboard = keyboard,
+            (false, Some(keyboard)) if keyboard.version() >= 3 => keyboard.release(),
+            (false, _) => {}
+        }
     }
 }
 
-impl<F> Dispatch<zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1, ()> for DiscoveryState<F>
+impl<F, B> Dispatch<wl_keyboard::WlKeyboard, ()> for DiscoveryState<F, B>
 where
-    F: FnMut(String) + Send + 'static,
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
 {
     fn event(
-        _: &mut Self,
-        _: &zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1,
-        _: zcosmic_toplevel_info_v1::Event,
+        state: &mut Self,
+        _: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        match event {
+            wl_keyboard::Event::Keymap {
+                format: WEnum::Value(wl_keyboard::KeymapFormat::XkbV1),
+                fd,
+                size,
+            } => {
+                // Read at an offset: the fd may be shared with other clients,
+                // so its position is too.
+                let mut raw = vec![0; size as usize];
+                if File::from(fd).read_exact_at(&mut raw, 0).is_err() {
+                    return;
+                }
+                let keymap = String::from_utf8_lossy(&raw)
+                    .trim_end_matches('\0')
+                    .to_string();
+
+                if state.reported_keymap.as_ref() != Some(&keymap) {
+                    state.reported_keymap = Some(keymap.clone());
+                    (state.on_event)(WatcherEvent::Keymap(keymap));
+                }
+            }
+            // the active layout rides along with the modifier state
+            wl_keyboard::Event::Modifiers { group, .. }
+                if state.reported_group != Some(group) =>
+            {
+                state.reported_group = Some(group);
+                (state.on_event)(WatcherEvent::KeyboardGroup(group));
+            }
+            _ => {}
+        }
     }
+}
 
-    fn event_created_child(_opcode: u16, qh: &QueueHandle<Self>) -> Arc<dyn ObjectData> {
-        qh.make_data::<zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1, ()>(())
+// Boilerplate
+impl<F, B> Dispatch<wl_registry::WlRegistry, GlobalListContents> for DiscoveryState<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: FocusBackend,
+{
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        // Outputs come and go with monitors.
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == wl_output::WlOutput::interface().name => {
+                state.bind_output(registry, name, version, qh);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                state.outputs.retain(|o| o.global != name);
+            }
+            _ => {}
+        }
     }
 }
@@ -0,0 +1,183 @@
+//! COSMIC: windows come from ext-foreign-toplevel-list, and each gets a
+//! `zcosmic_toplevel_handle_v1` for its state, outputs and workspaces.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use wayland_client::backend::ObjectData;
+use wayland_client::globals::GlobalList;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1, ext_foreign_toplevel_list_v1,
+};
+use cosmic_protocols::toplevel_info::v1::client::{
+    zcosmic_toplevel_handle_v1, zcosmic_toplevel_info_v1,
+};
+
+use super::ext_list::ExtListBacked;
+use super::{state_values, Backend, DiscoveryState, FocusBackend, WatcherEvent, Window};
+
+/// True if a `zcosmic_toplevel_handle_v1.state` array contains `state`.
+pub(super) fn has_state(raw: &[u8], state: zcosmic_toplevel_handle_v1::State) -> bool {
+    state_values(raw).any(|v| v == state as u32)
+}
+
+pub(super) struct Cosmic {
+    info: zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1,
+    _list: ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1,
+
+    // cosmic handle id -> (handle, ext handle id)
+    handles: HashMap<u32, (zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1, u32)>,
+}
+
+impl FocusBackend for Cosmic {
+    const KIND: Backend = Backend::Cosmic;
+
+    fn bind<F>(globals: &GlobalList, qh: &QueueHandle<DiscoveryState<F, Self>>) -> Option<Self>
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        // get_cosmic_toplevel needs v2. v3 reports workspaces as ext-workspace
+        // handles instead of the cosmic-workspace ones the watcher binds.
+        let info = globals
+            .bind::<zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1, _, _>(qh, 2..=2, ())
+            .ok()?;
+        let list = globals
+            .bind::<ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1, _, _>(qh, 1..=1, ())
+            .ok()?;
+
+        Some(Self {
+            info,
+            _list: list,
+            handles: HashMap::new(),
+        })
+    }
+}
+
+impl ExtListBacked for Cosmic {
+    fn toplevel_added<F>(
+        state: &mut DiscoveryState<F, Self>,
+        toplevel: &ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
+        qh: &QueueHandle<DiscoveryState<F, Self>>,
+    ) where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        // The cosmic handle carries the activated state.
+        let handle = state.backend.info.get_cosmic_toplevel(toplevel, qh, ());
+        let ext_id = toplevel.id().protocol_id();
+        state
+            .backend
+            .handles
+            .insert(handle.id().protocol_id(), (handle, ext_id));
+    }
+
+    fn toplevel_closed<F>(state: &mut DiscoveryState<F, Self>, ext_id: u32)
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        state.backend.handles.retain(|_, (handle, ext)| {
+            let keep = *ext != ext_id;
+            if !keep {
+                handle.destroy();
+            }
+            keep
+        });
+    }
+}
+
+impl<F> DiscoveryState<F, Cosmic>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+{
+    fn cosmic_window(&mut self, cosmic_id: u32) -> Option<&mut Window> {
+        let (_, ext_id) = self.backend.handles.get(&cosmic_id)?;
+        self.windows.get_mut(ext_id)
+    }
+
+    fn cosmic_state(&mut self, cosmic_id: u32, raw: &[u8]) {
+        use zcosmic_toplevel_handle_v1::State;
+
+        let Some(w) = self.cosmic_window(cosmic_id) else { return };
+        w.activated = has_state(raw, State::Activated);
+        w.maximized = has_state(raw, State::Maximized);
+        w.minimized = has_state(raw, State::Minimized);
+        w.fullscreen = has_state(raw, State::Fullscreen);
+    }
+}
+
+// State, outputs and workspaces
+impl<F> Dispatch<zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1, ()>
+    for DiscoveryState<F, Cosmic>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+{
+    fn event(
+        state: &mut Self,
+        proxy: &zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1,
+        event: zcosmic_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zcosmic_toplevel_handle_v1::Event;
+
+        let cosmic_id = proxy.id().protocol_id();
+
+        match event {
+            Event::State { state: raw_bytes } => state.cosmic_state(cosmic_id, &raw_bytes),
+
+            // v1 only; v2 sends these on the ext handle
+            Event::Done => {
+                if let Some((_, ext_id)) = state.backend.handles.get(&cosmic_id) {
+                    state.window_done(*ext_id);
+                }
+            }
+            Event::Closed => {
+                state.backend.handles.remove(&cosmic_id);
+            }
+
+            Event::OutputEnter { output } => {
+                if let Some(w) = state.cosmic_window(cosmic_id) {
+                    w.outputs.push(output.id().protocol_id());
+                }
+            }
+            Event::OutputLeave { output } => {
+                if let Some(w) = state.cosmic_window(cosmic_id) {
+                    w.outputs.retain(|id| *id != output.id().protocol_id());
+                }
+            }
+            Event::WorkspaceEnter { workspace } => {
+                if let Some(w) = state.cosmic_window(cosmic_id) {
+                    w.workspaces.push(workspace.id().protocol_id());
+                }
+            }
+            Event::WorkspaceLeave { workspace } => {
+                if let Some(w) = state.cosmic_window(cosmic_id) {
+                    w.workspaces.retain(|id| *id != workspace.id().protocol_id());
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+impl<F> Dispatch<zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1, ()> for DiscoveryState<F, Cosmic>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+{
+    fn event(
+        _: &mut Self,
+        _: &zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1,
+        _: zcosmic_toplevel_info_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+
+    fn event_created_child(_opcode: u16, qh: &QueueHandle<Self>) -> Arc<dyn ObjectData> {
+        qh.make_data::<zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1, ()>(())
+    }
+}
@@ -0,0 +1,170 @@
+//! ext-foreign-toplevel-list: window identity (app_id, title, identifier).
+//! The COSMIC backend builds on it; on its own it is the last resort, since it
+//! can't say which window is active.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use wayland_client::backend::ObjectData;
+use wayland_client::globals::GlobalList;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1, ext_foreign_toplevel_list_v1,
+};
+
+use super::{is_shell_surface, Backend, DiscoveryState, FocusBackend, WatcherEvent, Window};
+
+/// A backend whose windows are ext-foreign-toplevel handles, keyed by their
+/// protocol id. The hooks let it add its own state on top.
+pub(super) trait ExtListBacked: FocusBackend {
+    fn toplevel_added<F>(
+        _state: &mut DiscoveryState<F, Self>,
+        _toplevel: &ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
+        _qh: &QueueHandle<DiscoveryState<F, Self>>,
+    ) where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+    }
+
+    fn toplevel_done<F>(state: &mut DiscoveryState<F, Self>, id: u32)
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        state.window_done(id);
+    }
+
+    fn toplevel_closed<F>(_state: &mut DiscoveryState<F, Self>, _id: u32)
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+    }
+}
+
+pub(super) struct ExtList {
+    _list: ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1,
+
+    // announced, but not `done` yet
+    fresh: HashSet<u32>,
+}
+
+impl FocusBackend for ExtList {
+    const KIND: Backend = Backend::ExtList;
+
+    fn bind<F>(globals: &GlobalList, qh: &QueueHandle<DiscoveryState<F, Self>>) -> Option<Self>
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        let list = globals
+            .bind::<ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1, _, _>(qh, 1..=1, ())
+            .ok()?;
+
+        Some(Self {
+            _list: list,
+            fresh: HashSet::new(),
+        })
+    }
+}
+
+impl ExtListBacked for ExtList {
+    fn toplevel_added<F>(
+        state: &mut DiscoveryState<F, Self>,
+        toplevel: &ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
+        _: &QueueHandle<DiscoveryState<F, Self>>,
+    ) where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        state.backend.fresh.insert(toplevel.id().protocol_id());
+    }
+
+    /// No activated state here: a newly mapped window usually gets focus, so
+    /// treat the newest one as focused.
+    fn toplevel_done<F>(state: &mut DiscoveryState<F, Self>, id: u32)
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        if !state.backend.fresh.remove(&id) {
+            return;
+        }
+        if state
+            .windows
+            .get(&id)
+            .is_some_and(|w| !is_shell_surface(&w.app_id))
+        {
+            state.focused = Some(id);
+        }
+    }
+
+    fn toplevel_closed<F>(state: &mut DiscoveryState<F, Self>, id: u32)
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        state.backend.fresh.remove(&id);
+    }
+}
+
+// New window discovered
+impl<F, B> Dispatch<ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1, ()>
+    for DiscoveryState<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: ExtListBacked,
+{
+    fn event(
+        state: &mut Self,
+        _: &ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1,
+        event: ext_foreign_toplevel_list_v1::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } = event {
+            state
+                .windows
+                .insert(toplevel.id().protocol_id(), Window::default());
+            B::toplevel_added(state, &toplevel, qh);
+        }
+    }
+
+    fn event_created_child(_opcode: u16, qh: &QueueHandle<Self>) -> Arc<dyn ObjectData> {
+        qh.make_data::<ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1, ()>(())
+    }
+}
+
+// Identity, plus `done` for the whole window (cosmic v2+)
+impl<F, B> Dispatch<ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1, ()> for DiscoveryState<F, B>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+    B: ExtListBacked,
+{
+    fn event(
+        state: &mut Self,
+        proxy: &ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1,
+        event: ext_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use ext_foreign_toplevel_handle_v1::Event;
+
+        let id = proxy.id().protocol_id();
+
+        match event {
+            Event::Closed => {
+                B::toplevel_closed(state, id);
+                state.window_closed(id);
+                proxy.destroy();
+            }
+            Event::Done => B::toplevel_done(state, id),
+            ev => {
+                let Some(w) = state.windows.get_mut(&id) else { return };
+                match ev {
+                    Event::AppId { app_id } => w.app_id = app_id,
+                    Event::Title { title } => w.title = title,
+                    Event::Identifier { identifier } => w.identifier = identifier,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,904 @@
+//! In-process Wayland server for driving `FocusWatcher` in tests. It only
+//! speaks the protocols the watcher binds and is scripted from the test thread.
+//! Every toplevel sits on one output ("TEST-1") and the first workspace ("1"),
+//! which starts out active and floating. [`Protocols`] picks which toplevel
+//! protocols are advertised, to exercise each backend. The seat has a keyboard
+//! with [`KEYMAP`] until the test changes it.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use wayland_server::protocol::wl_keyboard::{self, WlKeyboard};
+use wayland_server::protocol::wl_output::{self, WlOutput};
+use wayland_server::protocol::wl_seat::{self, WlSeat};
+use wayland_server::{
+    Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use cosmic_protocols::toplevel_info::v1::server::{
+    zcosmic_toplevel_handle_v1, zcosmic_toplevel_info_v1,
+};
+use cosmic_protocols::workspace::v1::server::{
+    zcosmic_workspace_group_handle_v1, zcosmic_workspace_handle_v1, zcosmic_workspace_manager_v1,
+};
+use wayland_protocols::ext::foreign_toplevel_list::v1::server::{
+    ext_foreign_toplevel_handle_v1, ext_foreign_toplevel_list_v1,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::server::{
+    zwlr_foreign_toplevel_handle_v1, zwlr_foreign_toplevel_manager_v1,
+};
+
+use ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1 as ExtHandle;
+use ext_foreign_toplevel_list_v1::ExtForeignToplevelListV1 as ExtList;
+use zcosmic_toplevel_handle_v1::ZcosmicToplevelHandleV1 as CosmicHandle;
+use zcosmic_toplevel_info_v1::ZcosmicToplevelInfoV1 as CosmicInfo;
+use zcosmic_workspace_group_handle_v1::ZcosmicWorkspaceGroupHandleV1 as WorkspaceGroup;
+use zcosmic_workspace_handle_v1::ZcosmicWorkspaceHandleV1 as Workspace;
+use zcosmic_workspace_manager_v1::ZcosmicWorkspaceManagerV1 as WorkspaceManager;
+use zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1 as WlrHandle;
+use zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1 as WlrManager;
+
+pub const OUTPUT_NAME: &str = "TEST-1";
+pub const WORKSPACE_NAME: &str = "1";
+/// Stands in for an xkb keymap; the watcher passes it on unparsed.
+pub const KEYMAP: &str = "xkb_keymap { test }";
+
+/// Toplevel globals to advertise. Outputs are always there.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Protocols {
+    /// ext-foreign-toplevel-list, COSMIC toplevel info and workspaces
+    Cosmic,
+    /// wlr-foreign-toplevel-management only
+    Wlr,
+    /// ext-foreign-toplevel-list only
+    ExtList,
+    /// no toplevel protocol at all
+    None,
+}
+
+enum Command {
+    NewToplevel(u32, String),
+    SetAppId(u32, String),
+    SetTitle(u32, String),
+    SetMaximized(u32, bool),
+    Activate(u32),
+    Deactivate(u32),
+    Close(u32),
+    AddWorkspace(String),
+    ActivateWorkspace(String),
+    SetTiling(String, bool),
+    SetKeymap(String),
+    SetGroup(u32),
+}
+
+/// Handle to the server thread. Every call returns once the server has queued
+/// the resulting events; the client still has to roundtrip to see them.
+pub struct TestCompositor {
+    commands: Option<mpsc::Sender<(Command, mpsc::Sender<()>)>>,
+    thread: Option<thread::JoinHandle<()>>,
+    next_id: u32,
+}
+
+impl TestCompositor {
+    /// Start a COSMIC-like server and return it with the client end of the socket.
+    pub fn start() -> (Self, UnixStream) {
+        Self::start_with(Protocols::Cosmic)
+    }
+
+    pub fn start_with(protocols: Protocols) -> (Self, UnixStream) {
+        let (server_end, client_end) = UnixStream::pair().expect("socketpair");
+        let (tx, rx) = mpsc::channel();
+
+        let thread = thread::spawn(move || serve(server_end, protocols, rx));
+
+        let compositor = Self {
+            commands: Some(tx),
+            thread: Some(thread),
+            next_id: 1,
+        };
+        (compositor, client_end)
+    }
+
+    /// Map a new, unfocused window. Returns its id for later commands.
+    pub fn new_toplevel(&mut self, app_id: &str) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(Command::NewToplevel(id, app_id.to_string()));
+        id
+    }
+
+    pub fn set_app_id(&self, id: u32, app_id: &str) {
+        self.send(Command::SetAppId(id, app_id.to_string()));
+    }
+
+    pub fn set_title(&self, id: u32, title: &str) {
+        self.send(Command::SetTitle(id, title.to_string()));
+    }
+
+    pub fn set_maximized(&self, id: u32, maximized: bool) {
+        self.send(Command::SetMaximized(id, maximized));
+    }
+
+    /// Focus `id`; whichever window had focus loses it.
+    pub fn activate(&self, id: u32) {
+        self.send(Command::Activate(id));
+    }
+
+    pub fn deactivate(&self, id: u32) {
+        self.send(Command::Deactivate(id));
+    }
+
+    pub fn close(&self, id: u32) {
+        self.send(Command::Close(id));
+    }
+
+    /// Add an inactive, floating workspace.
+    pub fn add_workspace(&self, name: &str) {
+        self.send(Command::AddWorkspace(name.to_string()));
+    }
+
+    /// Make `name` the active workspace; the others become inactive.
+    pub fn activate_workspace(&self, name: &str) {
+        self.send(Command::ActivateWorkspace(name.to_string()));
+    }
+
+    pub fn set_tiling(&self, name: &str, tiling: bool) {
+        self.send(Command::SetTiling(name.to_string(), tiling));
+    }
+
+    /// Send every keyboard a new keymap, as a layout switch does.
+    pub fn set_keymap(&self, keymap: &str) {
+        self.send(Command::SetKeymap(keymap.to_string()));
+    }
+
+    /// Send every keyboard modifiers with `group` active, as switching
+    /// between the layouts of one keymap does.
+    pub fn set_group(&self, group: u32) {
+        self.send(Command::SetGroup(group));
+    }
+
+    fn send(&self, cmd: Command) {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        self.commands
+            .as_ref()
+            .expect("compositor running")
+            .send((cmd, ack_tx))
+            .expect("compositor thread alive");
+        ack_rx.recv().expect("compositor thread alive");
+    }
+}
+
+impl Drop for TestCompositor {
+    fn drop(&mut self) {
+        // Closing the channel ends the server loop.
+        self.commands.take();
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+#[derive(Default)]
+struct Toplevel {
+    app_id: String,
+    title: String,
+    activated: bool,
+    maximized: bool,
+    ext: Vec<ExtHandle>,
+    cosmic: Vec<CosmicHandle>,
+    wlr: Vec<WlrHandle>,
+}
+
+impl Toplevel {
+    fn state_bytes(&self) -> Vec<u8> {
+        use zcosmic_toplevel_handle_v1::State;
+
+        let mut states = Vec::new();
+        if self.maximized {
+            states.push(State::Maximized);
+        }
+        if self.activated {
+            states.push(State::Activated);
+        }
+        states
+            .into_iter()
+            .flat_map(|s| (s as u32).to_ne_bytes())
+            .collect()
+    }
+
+    fn wlr_state_bytes(&self) -> Vec<u8> {
+        use zwlr_foreign_toplevel_handle_v1::State;
+
+        let mut states = Vec::new();
+        if self.maximized {
+            states.push(State::Maximized);
+        }
+        if self.activated {
+            states.push(State::Activated);
+        }
+        states
+            .into_iter()
+            .flat_map(|s| (s as u32).to_ne_bytes())
+            .collect()
+    }
+
+    fn send_state(&self) {
+        for h in &self.cosmic {
+            h.state(self.state_bytes());
+        }
+        for h in &self.wlr {
+            h.state(self.wlr_state_bytes());
+        }
+        self.done();
+    }
+
+    /// v1 cosmic handles get their own `done`; from v2 the ext handle's counts.
+    fn done(&self) {
+        for h in &self.cosmic {
+            if h.version() < 2 {
+                h.done();
+            }
+        }
+        for h in &self.ext {
+            h.done();
+        }
+        for h in &self.wlr {
+            h.done();
+        }
+    }
+}
+
+struct TestWorkspace {
+    name: String,
+    active: bool,
+    tiling: bool,
+    handles: Vec<Workspace>,
+}
+
+impl TestWorkspace {
+    fn send_state(&self, handle: &Workspace) {
+        let state: &[u32] = if self.active {
+            &[zcosmic_workspace_handle_v1::State::Active as u32]
+        } else {
+            &[]
+        };
+        handle.state(state.iter().flat_map(|s| s.to_ne_bytes()).collect());
+        if handle.version() >= 2 {
+            handle.tiling_state(if self.tiling {
+                zcosmic_workspace_handle_v1::TilingState::TilingEnabled
+            } else {
+                zcosmic_workspace_handle_v1::TilingState::FloatingOnly
+            });
+        }
+    }
+}
+
+struct Server {
+    lists: Vec<ExtList>,
+    wlr_managers: Vec<WlrManager>,
+    toplevels: HashMap<u32, Toplevel>,
+    outputs: Vec<WlOutput>,
+    // one group per bound manager
+    groups: Vec<(WorkspaceManager, WorkspaceGroup)>,
+    workspaces: Vec<TestWorkspace>,
+    keyboards: Vec<WlKeyboard>,
+    keymap: String,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            lists: Vec::new(),
+            wlr_managers: Vec::new(),
+            toplevels: HashMap::new(),
+            outputs: Vec::new(),
+            groups: Vec::new(),
+            workspaces: vec![TestWorkspace {
+                name: WORKSPACE_NAME.to_string(),
+                active: true,
+                tiling: false,
+                handles: Vec::new(),
+            }],
+            keyboards: Vec::new(),
+            keymap: KEYMAP.to_string(),
+        }
+    }
+}
+
+fn serve(
+    stream: UnixStream,
+    protocols: Protocols,
+    commands: mpsc::Receiver<(Command, mpsc::Sender<()>)>,
+) {
+    let mut display = Display::<Server>::new().expect("wayland display");
+    let mut dh = display.handle();
+
+    match protocols {
+        Protocols::Cosmic => {
+            dh.create_global::<Server, ExtList, ()>(1, ());
+            dh.create_global::<Server, CosmicInfo, ()>(3, ());
+            dh.create_global::<Server, WorkspaceManager, ()>(2, ());
+        }
+        Protocols::Wlr => {
+            dh.create_global::<Server, WlrManager, ()>(3, ());
+        }
+        Protocols::ExtList => {
+            dh.create_global::<Server, ExtList, ()>(1, ());
+        }
+        Protocols::None => {}
+    }
+    dh.create_global::<Server, WlOutput, ()>(4, ());
+    dh.create_global::<Server, WlSeat, ()>(7, ());
+    dh.insert_client(stream, Arc::new(()))
+        .expect("insert client");
+
+    let mut server = Server::default();
+
+    loop {
+        loop {
+            match commands.try_recv() {
+                Ok((cmd, ack)) => {
+                    server.apply(&dh, cmd);
+                    display.flush_clients().ok();
+                    let _ = ack.send(());
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => return,
+            }
+        }
+
+        display.dispatch_clients(&mut server).ok();
+        display.flush_clients().ok();
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+impl Server {
+    fn apply(&mut self, dh: &DisplayHandle, cmd: Command) {
+        match cmd {
+            Command::NewToplevel(id, app_id) => {
+                let mut toplevel = Toplevel {
+                    app_id,
+                    ..Default::default()
+                };
+                for list in &self.lists {
+                    toplevel.ext.extend(announce(dh, list, id, &toplevel));
+                }
+                for manager in &self.wlr_managers {
+                    let handle = announce_wlr(dh, manager, &self.outputs, id, &toplevel);
+                    toplevel.wlr.extend(handle);
+                }
+                self.toplevels.insert(id, toplevel);
+            }
+
+            Command::SetAppId(id, app_id) => {
+                let Some(t) = self.toplevels.get_mut(&id) else {
+                    return;
+                };
+                for h in &t.ext {
+                    h.app_id(app_id.clone());
+                }
+                for h in &t.wlr {
+                    h.app_id(app_id.clone());
+                }
+                t.app_id = app_id;
+                t.done();
+            }
+
+            Command::SetTitle(id, title) => {
+                let Some(t) = self.toplevels.get_mut(&id) else {
+                    return;
+                };
+                for h in &t.ext {
+                    h.title(title.clone());
+                }
+                for h in &t.wlr {
+                    h.title(title.clone());
+                }
+                t.title = title;
+                t.done();
+            }
+
+            Command::SetMaximized(id, maximized) => {
+                let Some(t) = self.toplevels.get_mut(&id) else {
+                    return;
+                };
+                t.maximized = maximized;
+                t.send_state();
+            }
+
+            Command::Activate(id) => {
+                for (other, t) in self.toplevels.iter_mut() {
+                    let activated = *other == id;
+                    if t.activated != activated {
+                        t.activated = activated;
+                        t.send_state();
+                    }
+                }
+            }
+
+            Command::Deactivate(id) => {
+                let Some(t) = self.toplevels.get_mut(&id) else {
+                    return;
+                };
+                if t.activated {
+                    t.activated = false;
+                    t.send_state();
+                }
+            }
+
+            Command::Close(id) => {
+                let Some(t) = self.toplevels.remove(&id) else {
+                    return;
+                };
+                for h in &t.cosmic {
+                    if h.version() < 2 {
+                        h.closed();
+                    }
+                }
+                for h in &t.ext {
+                    h.closed();
+                }
+                for h in &t.wlr {
+                    h.closed();
+                }
+            }
+
+            Command::AddWorkspace(name) => {
+                let mut ws = TestWorkspace {
+                    name,
+                    active: false,
+                    tiling: false,
+                    handles: Vec::new(),
+                };
+                for (manager, group) in &self.groups {
+                    ws.handles
+                        .extend(announce_workspace(dh, manager, group, &ws));
+                    manager.done();
+                }
+                self.workspaces.push(ws);
+            }
+
+            Command::ActivateWorkspace(name) => {
+                for ws in &mut self.workspaces {
+                    ws.active = ws.name == name;
+                }
+                self.workspaces_changed();
+            }
+
+            Command::SetTiling(name, tiling) => {
+                for ws in self.workspaces.iter_mut().filter(|ws| ws.name == name) {
+                    ws.tiling = tiling;
+                }
+                self.workspaces_changed();
+            }
+
+            Command::SetKeymap(keymap) => {
+                for kb in &self.keyboards {
+                    send_keymap(kb, &keymap);
+                }
+                self.keymap = keymap;
+            }
+
+            Command::SetGroup(group) => {
+                for kb in &self.keyboards {
+                    kb.modifiers(0, 0, 0, 0, group);
+                }
+            }
+        }
+    }
+
+    fn workspaces_changed(&self) {
+        for ws in &self.workspaces {
+            for h in &ws.handles {
+                ws.send_state(h);
+            }
+        }
+        for (manager, _) in &self.groups {
+            manager.done();
+        }
+    }
+}
+
+/// Create a handle for `ws` in one manager's group and send its state.
+fn announce_workspace(
+    dh: &DisplayHandle,
+    manager: &WorkspaceManager,
+    group: &WorkspaceGroup,
+    ws: &TestWorkspace,
+) -> Option<Workspace> {
+    let client = manager.client()?;
+    let handle = client
+        .create_resource::<Workspace, (), Server>(dh, manager.version(), ())
+        .ok()?;
+    group.workspace(&handle);
+    handle.name(ws.name.clone());
+    ws.send_state(&handle);
+    Some(handle)
+}
+
+/// Create an ext handle for toplevel `id` on one list and send its initial state.
+fn announce(dh: &DisplayHandle, list: &ExtList, id: u32, t: &Toplevel) -> Option<ExtHandle> {
+    let client = list.client()?;
+    let handle = client
+        .create_resource::<ExtHandle, u32, Server>(dh, list.version(), id)
+        .ok()?;
+
+    list.toplevel(&handle);
+    handle.identifier(format!("toplevel-{id}"));
+    handle.app_id(t.app_id.clone());
+    handle.title(t.title.clone());
+    handle.done();
+
+    Some(handle)
+}
+
+/// Create a wlr handle for toplevel `id` and send its initial state.
+fn announce_wlr(
+    dh: &DisplayHandle,
+    manager: &WlrManager,
+    outputs: &[WlOutput],
+    id: u32,
+    t: &Toplevel,
+) -> Option<WlrHandle> {
+    let client = manager.client()?;
+    let handle = client
+        .create_resource::<WlrHandle, u32, Server>(dh, manager.version(), id)
+        .ok()?;
+
+    manager.toplevel(&handle);
+    handle.app_id(t.app_id.clone());
+    handle.title(t.title.clone());
+    for o in outputs {
+        handle.output_enter(o);
+    }
+    handle.state(t.wlr_state_bytes());
+    handle.done();
+
+    Some(handle)
+}
+
+impl GlobalDispatch<WlrManager, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        dh: &DisplayHandle,
+        _: &Client,
+        resource: New<WlrManager>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+        for (id, t) in state.toplevels.iter_mut() {
+            let handle = announce_wlr(dh, &manager, &state.outputs, *id, t);
+            t.wlr.extend(handle);
+        }
+        state.wlr_managers.push(manager);
+    }
+}
+
+impl Dispatch<WlrManager, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        resource: &WlrManager,
+        request: zwlr_foreign_toplevel_manager_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Request::Stop = request {
+            resource.finished();
+            state.wlr_managers.retain(|m| m != resource);
+        }
+    }
+}
+
+impl Dispatch<WlrHandle, u32> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlrHandle,
+        _: zwlr_foreign_toplevel_handle_v1::Request,
+        _: &u32,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<ExtList, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        dh: &DisplayHandle,
+        _: &Client,
+        resource: New<ExtList>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let list = data_init.init(resource, ());
+        for (id, t) in state.toplevels.iter_mut() {
+            let handle = announce(dh, &list, *id, t);
+            t.ext.extend(handle);
+        }
+        state.lists.push(list);
+    }
+}
+
+impl Dispatch<ExtList, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        resource: &ExtList,
+        request: ext_foreign_toplevel_list_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let ext_foreign_toplevel_list_v1::Request::Stop = request {
+            resource.finished();
+            state.lists.retain(|l| l != resource);
+        }
+    }
+}
+
+impl Dispatch<ExtHandle, u32> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &ExtHandle,
+        _: ext_foreign_toplevel_handle_v1::Request,
+        _: &u32,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<CosmicInfo, ()> for Server {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<CosmicInfo>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<CosmicInfo, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &CosmicInfo,
+        request: zcosmic_toplevel_info_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zcosmic_toplevel_info_v1::Request::GetCosmicToplevel {
+            cosmic_toplevel,
+            foreign_toplevel,
+        } = request
+        {
+            let id = *foreign_toplevel.data::<u32>().expect("ext handle data");
+            let handle = data_init.init(cosmic_toplevel, id);
+
+            let Some(t) = state.toplevels.get_mut(&id) else {
+                return;
+            };
+            handle.state(t.state_bytes());
+            for o in &state.outputs {
+                handle.output_enter(o);
+            }
+            // v3 clients would get ext_workspace_enter instead
+            if handle.version() < 3 {
+                for w in &state.workspaces[0].handles {
+                    handle.workspace_enter(w);
+                }
+            }
+            t.cosmic.push(handle);
+            t.done();
+        }
+    }
+}
+
+impl Dispatch<CosmicHandle, u32> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &CosmicHandle,
+        _: zcosmic_toplevel_handle_v1::Request,
+        _: &u32,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<WlOutput, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlOutput>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let output = data_init.init(resource, ());
+        if output.version() >= 4 {
+            output.name(OUTPUT_NAME.to_string());
+        }
+        if output.version() >= 2 {
+            output.done();
+        }
+        state.outputs.push(output);
+    }
+}
+
+impl Dispatch<WlOutput, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        resource: &WlOutput,
+        request: wl_output::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_output::Request::Release = request {
+            state.outputs.retain(|o| o != resource);
+        }
+    }
+}
+
+impl GlobalDispatch<WorkspaceManager, ()> for Server {
+    fn bind(
+        state: &mut Self,
+        dh: &DisplayHandle,
+        client: &Client,
+        resource: New<WorkspaceManager>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let Ok(group) =
+            client.create_resource::<WorkspaceGroup, (), Server>(dh, manager.version(), ())
+        else {
+            return;
+        };
+        manager.workspace_group(&group);
+
+        for ws in &mut state.workspaces {
+            let handle = announce_workspace(dh, &manager, &group, ws);
+            ws.handles.extend(handle);
+        }
+        manager.done();
+
+        state.groups.push((manager, group));
+    }
+}
+
+impl Dispatch<WorkspaceManager, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WorkspaceManager,
+        _: zcosmic_workspace_manager_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WorkspaceGroup, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WorkspaceGroup,
+        _: zcosmic_workspace_group_handle_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<Workspace, ()> for Server {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &Workspace,
+        _: zcosmic_workspace_handle_v1::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+/// Hand `keyboard` a file with `keymap`, NUL-terminated like real ones. Its
+/// position is left at the end, as a compositor sharing one fd would.
+fn send_keymap(keyboard: &WlKeyboard, keymap: &str) {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let path = std::env::temp_dir().join(format!(
+        "orbitkeys-keymap-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .expect("keymap file");
+    let _ = fs::remove_file(&path);
+
+    let raw = [keymap.as_bytes(), b"\0"].concat();
+    file.write_all(&raw).expect("write keymap");
+    keyboard.keymap(
+        wl_keyboard::KeymapFormat::XkbV1,
+        file.as_fd(),
+        raw.len() as u32,
+    );
+}
+
+impl GlobalDispatch<WlSeat, ()> for Server {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlSeat>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let seat = data_init.init(resource, ());
+        seat.capabilities(wl_seat::Capability::Keyboard);
+    }
+}
+
+impl Dispatch<WlSeat, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        _: &WlSeat,
+        request: wl_seat::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_seat::Request::GetKeyboard { id } = request {
+            let keyboard = data_init.init(id, ());
+            send_keymap(&keyboard, &state.keymap);
+            state.keyboards.push(keyboard);
+        }
+    }
+}
+
+impl Dispatch<WlKeyboard, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _: &Client,
+        resource: &WlKeyboard,
+        request: wl_keyboard::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_keyboard::Request::Release = request {
+            state.keyboards.retain(|k| k != resource);
+        }
+    }
+}
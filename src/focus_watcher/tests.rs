@@ -0,0 +1,686 @@
+use std::os::unix::net::UnixStream;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use cosmic_protocols::toplevel_info::v1::client::zcosmic_toplevel_handle_v1::State;
+use wayland_client::Connection;
+
+use super::cosmic::has_state;
+use super::test_compositor::{Protocols, TestCompositor, KEYMAP, OUTPUT_NAME, WORKSPACE_NAME};
+use super::{
+    watch, Backend, Backoff, FocusEvent, FocusEventKind, FocusWatcher, WatcherEvent, WorkspaceEvent,
+};
+use crate::shutdown::Shutdown;
+
+type Seen<T> = Arc<Mutex<Vec<T>>>;
+
+/// What a watcher reported, sorted by event type.
+#[derive(Default, Clone)]
+struct Sinks {
+    backends: Seen<Option<Backend>>,
+    focus: Seen<FocusEvent>,
+    workspaces: Seen<WorkspaceEvent>,
+    keymaps: Seen<String>,
+    groups: Seen<u32>,
+    disconnects: Seen<(String, Duration)>,
+}
+
+impl Sinks {
+    fn callback(&self) -> impl FnMut(WatcherEvent) + Clone + Send + 'static {
+        let sinks = self.clone();
+        move |ev| match ev {
+            WatcherEvent::Backend(b) => sinks.backends.lock().unwrap().push(b),
+            WatcherEvent::Focus(ev) => sinks.focus.lock().unwrap().push(ev),
+            WatcherEvent::Workspace(ev) => sinks.workspaces.lock().unwrap().push(ev),
+            WatcherEvent::Keymap(k) => sinks.keymaps.lock().unwrap().push(k),
+            WatcherEvent::KeyboardGroup(g) => sinks.groups.lock().unwrap().push(g),
+            WatcherEvent::Disconnected { error, retry_in } => {
+                sinks.disconnects.lock().unwrap().push((error, retry_in))
+            }
+        }
+    }
+}
+
+/// Wait (a while) for another thread to make `cond` true.
+fn wait_for(what: &str, cond: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !cond() {
+        assert!(Instant::now() < deadline, "timed out waiting for {what}");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+struct Harness {
+    compositor: TestCompositor,
+    watcher: FocusWatcher,
+    seen: Seen<FocusEvent>,
+    workspaces: Seen<WorkspaceEvent>,
+    keymaps: Seen<String>,
+    groups: Seen<u32>,
+    backends: Seen<Option<Backend>>,
+}
+
+impl Harness {
+    fn start() -> Self {
+        Self::start_with(Protocols::Cosmic)
+    }
+
+    fn start_with(protocols: Protocols) -> Self {
+        let (compositor, socket) = TestCompositor::start_with(protocols);
+        Self::connect(compositor, socket)
+    }
+
+    fn connect(compositor: TestCompositor, socket: std::os::unix::net::UnixStream) -> Self {
+        let conn = Connection::from_socket(socket).expect("connect to test compositor");
+
+        let sinks = Sinks::default();
+        let watcher = FocusWatcher::new(&conn, sinks.callback())
+            .expect("bind toplevel globals")
+            .expect("a supported backend");
+
+        let mut h = Self {
+            compositor,
+            watcher,
+            seen: sinks.focus,
+            workspaces: sinks.workspaces,
+            keymaps: sinks.keymaps,
+            groups: sinks.groups,
+            backends: sinks.backends,
+        };
+        h.sync();
+        h
+    }
+
+    /// Two roundtrips: the first delivers new toplevels, which makes the
+    /// watcher request cosmic handles; the second delivers their state.
+    fn sync(&mut self) {
+        self.watcher.roundtrip().unwrap();
+        self.watcher.roundtrip().unwrap();
+    }
+
+    /// Events since the last call.
+    fn events(&mut self) -> Vec<FocusEvent> {
+        self.sync();
+        std::mem::take(&mut *self.seen.lock().unwrap())
+    }
+
+    /// Events since the last call, as "Kind app_id".
+    fn take(&mut self) -> Vec<String> {
+        self.events()
+            .iter()
+            .map(|e| format!("{:?} {}", e.kind, e.app_id))
+            .collect()
+    }
+
+    /// Workspace events since the last call, as "name tiled|floating".
+    fn take_workspaces(&mut self) -> Vec<String> {
+        self.sync();
+        std::mem::take(&mut *self.workspaces.lock().unwrap())
+            .iter()
+            .map(|e| {
+                let layout = match e.tiling {
+                    Some(true) => "tiled",
+                    Some(false) => "floating",
+                    None => "unknown",
+                };
+                format!("{} {layout}", e.name.as_deref().unwrap_or("?"))
+            })
+            .collect()
+    }
+
+    /// Keymaps since the last call.
+    fn take_keymaps(&mut self) -> Vec<String> {
+        self.sync();
+        std::mem::take(&mut *self.keymaps.lock().unwrap())
+    }
+
+    /// Keyboard groups since the last call.
+    fn take_groups(&mut self) -> Vec<u32> {
+        self.sync();
+        std::mem::take(&mut *self.groups.lock().unwrap())
+    }
+}
+
+#[test]
+fn state_decoding() {
+    let activated = (State::Activated as u32).to_ne_bytes();
+    let maximized = (State::Maximized as u32).to_ne_bytes();
+
+    assert!(!has_state(&[], State::Activated));
+    assert!(!has_state(&maximized, State::Activated));
+    assert!(has_state(&activated, State::Activated));
+    assert!(has_state(
+        &[maximized, activated].concat(),
+        State::Activated
+    ));
+    assert!(has_state(
+        &[maximized, activated].concat(),
+        State::Maximized
+    ));
+    // A trailing partial value is ignored rather than misread.
+    assert!(!has_state(&activated[..3], State::Activated));
+}
+
+#[test]
+fn mapping_a_window_does_not_report_focus() {
+    let mut h = Harness::start();
+    h.compositor.new_toplevel("firefox");
+    assert!(h.take().is_empty());
+}
+
+#[test]
+fn activation_reports_the_window() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    h.compositor.set_title(ff, "Mozilla Firefox");
+    h.compositor.set_maximized(ff, true);
+    h.sync();
+
+    h.compositor.activate(ff);
+    assert_eq!(
+        h.events(),
+        [FocusEvent {
+            kind: FocusEventKind::Activated,
+            app_id: "firefox".into(),
+            title: "Mozilla Firefox".into(),
+            identifier: format!("toplevel-{ff}"),
+            maximized: true,
+            minimized: false,
+            fullscreen: false,
+            output: Some(OUTPUT_NAME.into()),
+            workspace: Some(WORKSPACE_NAME.into()),
+        }]
+    );
+}
+
+#[test]
+fn switching_windows_reports_each_app() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    let term = h.compositor.new_toplevel("com.system76.CosmicTerm");
+    h.sync();
+
+    h.compositor.activate(ff);
+    h.sync();
+    h.compositor.activate(term);
+    h.sync();
+    h.compositor.activate(ff);
+    assert_eq!(
+        h.take(),
+        [
+            "Activated firefox",
+            "Activated com.system76.CosmicTerm",
+            "Activated firefox"
+        ]
+    );
+}
+
+#[test]
+fn switching_between_windows_of_one_app_reports_each() {
+    let mut h = Harness::start();
+    let a = h.compositor.new_toplevel("firefox");
+    let b = h.compositor.new_toplevel("firefox");
+    h.sync();
+
+    h.compositor.activate(a);
+    h.sync();
+    h.compositor.activate(b);
+    h.sync();
+    h.compositor.activate(a);
+
+    let ids: Vec<_> = h.events().into_iter().map(|e| e.identifier).collect();
+    assert_eq!(
+        ids,
+        [
+            format!("toplevel-{a}"),
+            format!("toplevel-{b}"),
+            format!("toplevel-{a}")
+        ]
+    );
+}
+
+#[test]
+fn changes_within_one_batch_are_coalesced() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    let term = h.compositor.new_toplevel("com.system76.CosmicTerm");
+    h.sync();
+
+    // Passes through "nothing focused" and back without a FocusLost.
+    h.compositor.activate(ff);
+    h.compositor.deactivate(ff);
+    h.compositor.activate(term);
+    assert_eq!(h.take(), ["Activated com.system76.CosmicTerm"]);
+}
+
+#[test]
+fn refocusing_the_same_window_is_reported_once() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    h.sync();
+
+    h.compositor.activate(ff);
+    h.sync();
+    h.compositor.activate(ff);
+    assert_eq!(h.take(), ["Activated firefox"]);
+}
+
+#[test]
+fn deactivating_reports_focus_lost() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    h.compositor.set_title(ff, "Mozilla Firefox");
+    h.sync();
+
+    h.compositor.activate(ff);
+    assert_eq!(h.take(), ["Activated firefox"]);
+
+    h.compositor.deactivate(ff);
+    let events = h.events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, FocusEventKind::FocusLost);
+    assert_eq!(events[0].title, "Mozilla Firefox");
+    assert_eq!(events[0].identifier, format!("toplevel-{ff}"));
+
+    // Coming back is a fresh activation.
+    h.compositor.activate(ff);
+    assert_eq!(h.take(), ["Activated firefox"]);
+}
+
+#[test]
+fn shell_overlays_count_as_no_focus() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    let launcher = h.compositor.new_toplevel("com.system76.CosmicLauncher");
+    h.sync();
+
+    h.compositor.activate(ff);
+    h.sync();
+    h.compositor.activate(launcher);
+    h.sync();
+    h.compositor.activate(ff);
+    assert_eq!(
+        h.take(),
+        [
+            "Activated firefox",
+            "FocusLost firefox",
+            "Activated firefox"
+        ]
+    );
+}
+
+#[test]
+fn app_id_change_applies_on_next_activation() {
+    let mut h = Harness::start();
+    let w = h.compositor.new_toplevel("");
+    let other = h.compositor.new_toplevel("firefox");
+    h.sync();
+
+    h.compositor.activate(w);
+    assert_eq!(h.take(), ["Activated "]);
+
+    h.compositor.set_app_id(w, "org.gnome.Nautilus");
+    assert!(h.take().is_empty());
+
+    h.compositor.activate(other);
+    h.sync();
+    h.compositor.activate(w);
+    assert_eq!(
+        h.take(),
+        ["Activated firefox", "Activated org.gnome.Nautilus"]
+    );
+}
+
+#[test]
+fn title_changes_of_the_active_window_are_reported() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    let term = h.compositor.new_toplevel("com.system76.CosmicTerm");
+    h.sync();
+
+    h.compositor.activate(ff);
+    h.sync();
+    h.compositor.set_title(ff, "Inbox — Mozilla Firefox");
+    h.sync();
+    // not focused: ignored
+    h.compositor.set_title(term, "vim");
+    h.sync();
+    // unchanged: ignored
+    h.compositor.set_title(ff, "Inbox — Mozilla Firefox");
+
+    let events = h.events();
+    let summary: Vec<_> = events.iter().map(|e| (e.kind, e.title.as_str())).collect();
+    assert_eq!(
+        summary,
+        [
+            (FocusEventKind::Activated, ""),
+            (FocusEventKind::TitleChanged, "Inbox — Mozilla Firefox"),
+        ]
+    );
+}
+
+#[test]
+fn title_tracking_follows_the_active_window() {
+    let mut h = Harness::start();
+    let a = h.compositor.new_toplevel("firefox");
+    let b = h.compositor.new_toplevel("firefox");
+    h.compositor.set_title(b, "Docs");
+    h.sync();
+
+    h.compositor.activate(a);
+    h.sync();
+    h.compositor.activate(b);
+    // b's existing title arrives with its activation, not as a change
+    let events = h.events();
+    let summary: Vec<_> = events.iter().map(|e| (e.kind, e.title.as_str())).collect();
+    assert_eq!(
+        summary,
+        [
+            (FocusEventKind::Activated, ""),
+            (FocusEventKind::Activated, "Docs"),
+        ]
+    );
+
+    h.compositor.set_title(a, "Mail");
+    assert!(h.take().is_empty());
+    h.compositor.set_title(b, "Sheets");
+    assert_eq!(h.take(), ["TitleChanged firefox"]);
+}
+
+#[test]
+fn closing_windows_is_reported() {
+    let mut h = Harness::start();
+    let ff = h.compositor.new_toplevel("firefox");
+    let term = h.compositor.new_toplevel("com.system76.CosmicTerm");
+    h.sync();
+
+    h.compositor.activate(ff);
+    h.sync();
+    // an unfocused window
+    h.compositor.close(term);
+    h.sync();
+    // the focused one, leaving nothing focused
+    h.compositor.close(ff);
+    assert_eq!(
+        h.take(),
+        [
+            "Activated firefox",
+            "Closed com.system76.CosmicTerm",
+            "Closed firefox",
+            "FocusLost firefox"
+        ]
+    );
+}
+
+#[test]
+fn closing_the_focused_window_hands_focus_over() {
+    let mut h = Harness::start();
+    let a = h.compositor.new_toplevel("firefox");
+    let b = h.compositor.new_toplevel("firefox");
+    h.sync();
+
+    h.compositor.activate(a);
+    h.sync();
+    h.compositor.close(a);
+    h.compositor.activate(b);
+    let events = h.events();
+    let summary: Vec<_> = events
+        .iter()
+        .map(|e| (e.kind, e.identifier.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (FocusEventKind::Activated, format!("toplevel-{a}")),
+            (FocusEventKind::Closed, format!("toplevel-{a}")),
+            (FocusEventKind::Activated, format!("toplevel-{b}")),
+        ]
+    );
+}
+
+#[test]
+fn windows_mapped_before_connect_are_tracked() {
+    let (mut compositor, socket) = TestCompositor::start();
+    let ff = compositor.new_toplevel("firefox");
+
+    let mut h = Harness::connect(compositor, socket);
+    h.compositor.activate(ff);
+    assert_eq!(h.take(), ["Activated firefox"]);
+}
+
+#[test]
+fn the_active_workspace_is_reported_on_connect() {
+    let mut h = Harness::start();
+    assert_eq!(h.take_workspaces(), ["1 floating"]);
+}
+
+#[test]
+fn workspace_switches_and_tiling_changes_are_reported() {
+    let mut h = Harness::start();
+    h.compositor.add_workspace("2");
+    // adding an inactive workspace changes nothing
+    assert_eq!(h.take_workspaces(), ["1 floating"]);
+
+    h.compositor.set_tiling("2", true);
+    assert!(h.take_workspaces().is_empty());
+
+    h.compositor.activate_workspace("2");
+    h.sync();
+    h.compositor.set_tiling("2", false);
+    h.sync();
+    h.compositor.activate_workspace("1");
+    assert_eq!(h.take_workspaces(), ["2 tiled", "2 floating", "1 floating"]);
+}
+
+#[test]
+fn keymap_is_reported_once_per_change() {
+    let mut h = Harness::start();
+    assert_eq!(h.take_keymaps(), [KEYMAP]);
+
+    // resent unchanged, e.g. to a second keyboard
+    h.compositor.set_keymap(KEYMAP);
+    assert!(h.take_keymaps().is_empty());
+
+    h.compositor.set_keymap("xkb_keymap { de }");
+    assert_eq!(h.take_keymaps(), ["xkb_keymap { de }"]);
+}
+
+#[test]
+fn keyboard_group_is_reported_once_per_change() {
+    let mut h = Harness::start();
+    assert!(h.take_groups().is_empty());
+
+    h.compositor.set_group(1);
+    assert_eq!(h.take_groups(), [1]);
+
+    // other modifiers changed, the group didn't
+    h.compositor.set_group(1);
+    assert!(h.take_groups().is_empty());
+
+    h.compositor.set_group(0);
+    assert_eq!(h.take_groups(), [0]);
+}
+
+#[test]
+fn shutdown_unblocks_dispatch() {
+    let mut h = Harness::start();
+    let shutdown = Shutdown::new().unwrap();
+
+    let stop = shutdown.clone();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        stop.stop();
+    });
+
+    // Nothing to read, so this blocks until the stop arrives.
+    assert!(!h.watcher.dispatch(&shutdown).unwrap());
+    stopper.join().unwrap();
+
+    // Stays stopped.
+    assert!(!h.watcher.dispatch(&shutdown).unwrap());
+}
+
+#[test]
+fn dispatch_delivers_focus_until_stopped() {
+    let mut h = Harness::start();
+    let shutdown = Shutdown::new().unwrap();
+    let ff = h.compositor.new_toplevel("firefox");
+    h.sync();
+
+    h.compositor.activate(ff);
+    while h.seen.lock().unwrap().is_empty() {
+        assert!(h.watcher.dispatch(&shutdown).unwrap());
+    }
+    assert_eq!(h.take(), ["Activated firefox"]);
+}
+
+#[test]
+fn the_cosmic_backend_is_preferred() {
+    let h = Harness::start();
+    assert_eq!(*h.backends.lock().unwrap(), [Some(Backend::Cosmic)]);
+}
+
+#[test]
+fn the_wlr_backend_reports_activation() {
+    let mut h = Harness::start_with(Protocols::Wlr);
+    assert_eq!(*h.backends.lock().unwrap(), [Some(Backend::Wlr)]);
+
+    let ff = h.compositor.new_toplevel("firefox");
+    let term = h.compositor.new_toplevel("com.system76.CosmicTerm");
+    h.compositor.set_title(ff, "Mozilla Firefox");
+    h.compositor.set_maximized(ff, true);
+    assert!(h.take().is_empty());
+
+    h.compositor.activate(ff);
+    let ev = h.events();
+    assert_eq!(ev.len(), 1);
+    assert_eq!(ev[0].kind, FocusEventKind::Activated);
+    assert_eq!(ev[0].title, "Mozilla Firefox");
+    assert!(ev[0].maximized);
+    assert_eq!(ev[0].output.as_deref(), Some(OUTPUT_NAME));
+    // no workspaces in this protocol
+    assert_eq!(ev[0].workspace, None);
+
+    h.compositor.activate(term);
+    h.sync();
+    h.compositor.close(term);
+    assert_eq!(
+        h.take(),
+        [
+            "Activated com.system76.CosmicTerm",
+            "Closed com.system76.CosmicTerm",
+            "FocusLost com.system76.CosmicTerm"
+        ]
+    );
+    assert!(h.take_workspaces().is_empty());
+}
+
+#[test]
+fn ext_list_only_assumes_the_newest_window_has_focus() {
+    let mut h = Harness::start_with(Protocols::ExtList);
+    assert_eq!(*h.backends.lock().unwrap(), [Some(Backend::ExtList)]);
+
+    let ff = h.compositor.new_toplevel("firefox");
+    assert_eq!(h.take(), ["Activated firefox"]);
+
+    let term = h.compositor.new_toplevel("com.system76.CosmicTerm");
+    assert_eq!(h.take(), ["Activated com.system76.CosmicTerm"]);
+
+    // activation isn't visible here
+    h.compositor.activate(ff);
+    assert!(h.take().is_empty());
+
+    h.compositor.close(term);
+    assert_eq!(
+        h.take(),
+        [
+            "Closed com.system76.CosmicTerm",
+            "FocusLost com.system76.CosmicTerm"
+        ]
+    );
+}
+
+#[test]
+fn no_supported_protocol_is_reported() {
+    let (_compositor, socket) = TestCompositor::start_with(Protocols::None);
+    let conn = Connection::from_socket(socket).expect("connect to test compositor");
+
+    let sinks = Sinks::default();
+    let watcher = FocusWatcher::new(&conn, sinks.callback()).expect("registry");
+    assert!(watcher.is_none());
+    assert_eq!(*sinks.backends.lock().unwrap(), [None]);
+}
+
+#[test]
+fn failed_connects_back_off() {
+    let shutdown = Shutdown::new().unwrap();
+    let sinks = Sinks::default();
+    let backoff = Backoff {
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(4),
+    };
+
+    let disconnects = sinks.disconnects.clone();
+    let stop = shutdown.clone();
+    let connect = move || {
+        if disconnects.lock().unwrap().len() == 4 {
+            stop.stop();
+        }
+        anyhow::bail!("no compositor")
+    };
+    watch(&shutdown, backoff, connect, sinks.callback());
+
+    let ms = |n| ("no compositor".to_string(), Duration::from_millis(n));
+    assert_eq!(
+        *sinks.disconnects.lock().unwrap(),
+        [ms(1), ms(2), ms(4), ms(4), ms(4)]
+    );
+    assert!(sinks.backends.lock().unwrap().is_empty());
+}
+
+#[test]
+fn the_watcher_reconnects_when_the_compositor_restarts() {
+    let shutdown = Shutdown::new().unwrap();
+    let sinks = Sinks::default();
+    let backoff = Backoff {
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(1),
+    };
+
+    let (sockets, incoming) = mpsc::channel::<UnixStream>();
+    let connect = move || Ok(Connection::from_socket(incoming.recv()?)?);
+    let watcher = {
+        let (shutdown, callback) = (shutdown.clone(), sinks.callback());
+        std::thread::spawn(move || watch(&shutdown, backoff, connect, callback))
+    };
+    let focused = |app_id: &str| {
+        let focus = sinks.focus.lock().unwrap();
+        focus.last().is_some_and(|e| e.app_id == app_id)
+    };
+
+    let (mut first, socket) = TestCompositor::start();
+    sockets.send(socket).unwrap();
+    let ff = first.new_toplevel("firefox");
+    first.activate(ff);
+    wait_for("firefox", || focused("firefox"));
+
+    drop(first);
+    wait_for("the disconnect", || {
+        !sinks.disconnects.lock().unwrap().is_empty()
+    });
+
+    let (mut second, socket) = TestCompositor::start();
+    let term = second.new_toplevel("com.system76.CosmicTerm");
+    second.activate(term);
+    sockets.send(socket).unwrap();
+    wait_for("the terminal", || focused("com.system76.CosmicTerm"));
+
+    assert_eq!(sinks.disconnects.lock().unwrap().len(), 1);
+    assert_eq!(
+        *sinks.backends.lock().unwrap(),
+        [Some(Backend::Cosmic), Some(Backend::Cosmic)]
+    );
+
+    shutdown.stop();
+    watcher.join().unwrap();
+}
@@ -0,0 +1,115 @@
+//! wlr-foreign-toplevel-management: one handle per window with its identity,
+//! state and outputs. There are no workspaces or stable identifiers.
+
+use std::sync::Arc;
+
+use wayland_client::backend::ObjectData;
+use wayland_client::globals::GlobalList;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1, zwlr_foreign_toplevel_manager_v1,
+};
+
+use super::{state_values, Backend, DiscoveryState, FocusBackend, WatcherEvent, Window};
+
+pub(super) struct Wlr {
+    _manager: zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1,
+}
+
+impl FocusBackend for Wlr {
+    const KIND: Backend = Backend::Wlr;
+
+    fn bind<F>(globals: &GlobalList, qh: &QueueHandle<DiscoveryState<F, Self>>) -> Option<Self>
+    where
+        F: FnMut(WatcherEvent) + Send + 'static,
+    {
+        let manager = globals
+            .bind::<zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1, _, _>(
+                qh,
+                1..=3,
+                (),
+            )
+            .ok()?;
+
+        Some(Self { _manager: manager })
+    }
+}
+
+impl<F> Dispatch<zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1, ()>
+    for DiscoveryState<F, Wlr>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+{
+    fn event(
+        state: &mut Self,
+        _: &zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } = event {
+            let id = toplevel.id().protocol_id();
+            state.windows.insert(
+                id,
+                Window {
+                    identifier: format!("wlr-{id}"),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    fn event_created_child(_opcode: u16, qh: &QueueHandle<Self>) -> Arc<dyn ObjectData> {
+        qh.make_data::<zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1, ()>(())
+    }
+}
+
+impl<F> Dispatch<zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1, ()>
+    for DiscoveryState<F, Wlr>
+where
+    F: FnMut(WatcherEvent) + Send + 'static,
+{
+    fn event(
+        state: &mut Self,
+        proxy: &zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::{Event, State};
+
+        let id = proxy.id().protocol_id();
+
+        match event {
+            Event::Done => state.window_done(id),
+            Event::Closed => {
+                state.window_closed(id);
+                proxy.destroy();
+            }
+            ev => {
+                let Some(w) = state.windows.get_mut(&id) else {
+                    return;
+                };
+                match ev {
+                    Event::Title { title } => w.title = title,
+                    Event::AppId { app_id } => w.app_id = app_id,
+                    Event::State { state: raw } => {
+                        let has = |s: State| state_values(&raw).any(|v| v == s as u32);
+                        w.activated = has(State::Activated);
+                        w.maximized = has(State::Maximized);
+                        w.minimized = has(State::Minimized);
+                        w.fullscreen = has(State::Fullscreen);
+                    }
+                    Event::OutputEnter { output } => w.outputs.push(output.id().protocol_id()),
+                    Event::OutputLeave { output } => {
+                        w.outputs.retain(|o| *o != output.id().protocol_id());
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
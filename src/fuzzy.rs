@@ -0,0 +1,268 @@
+//! Ranked fuzzy matching for the search box. A query is split into terms,
+//! and an entry is found when every term matches its description, its
+//! category or one of its keys. Text fields match, best first:
+//!
+//! - as a substring, best as a whole word: "as" in "Save As"
+//! - letter by letter with few gaps: "sve" in "Save"
+//! - one typo away from a word: "saev" for "save"
+//!
+//! Keys match by label, so "ctrl shift p" finds Ctrl+Shift+P; glyphs are read
+//! as their labels, making "⌃⇧P" the same query.
+
+use std::ops::Range;
+
+use crate::key_glyphs::KeyGlyph;
+
+#[cfg(test)]
+mod tests;
+
+/// A parsed search query.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Lowercased.
+    terms: Vec<Vec<char>>,
+}
+
+/// The parts of an entry a query matches against.
+#[derive(Debug, Clone, Copy)]
+pub struct Fields<'a> {
+    pub desc: &'a str,
+    pub category: &'a str,
+    /// Lowercased key labels, as `KeySequence::search_tokens` gives them.
+    pub keys: &'a [String],
+}
+
+/// How well an entry matched; higher scores rank first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Found {
+    pub score: u32,
+    /// What to highlight in the description, as ranges of char indices.
+    pub desc_spans: Vec<Range<usize>>,
+}
+
+impl Query {
+    /// Terms are separated by spaces or "+"; each key glyph is a term too.
+    /// A "+" standing alone, or doubled as in "ctrl++", is the Plus key.
+    pub fn parse(raw: &str) -> Self {
+        let mut terms: Vec<Vec<char>> = Vec::new();
+        let mut push = |term: Vec<char>| {
+            if !term.is_empty() && terms.last() != Some(&term) {
+                terms.push(term);
+            }
+        };
+
+        for word in raw.split_whitespace() {
+            let (word, plus) = match word.strip_suffix("++") {
+                Some(head) => (head, true),
+                None => (word, word == "+"),
+            };
+
+            for part in word.split('+') {
+                let mut term = Vec::new();
+                for c in part.chars() {
+                    // "+" is a glyph too, but split on above
+                    match KeyGlyph::from_glyph(c).filter(|_| !c.is_ascii()) {
+                        Some(g) => {
+                            push(std::mem::take(&mut term));
+                            push(lower(g.label()));
+                        }
+                        None => term.push(lower_char(c)),
+                    }
+                }
+                push(term);
+            }
+            if plus {
+                push(lower(KeyGlyph::Plus.label()));
+            }
+        }
+        Self { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Score `fields`, or `None` if a term matches none of them. Each term
+    /// counts where it matches best; the description wins ties, and only
+    /// its wins are highlighted.
+    pub fn find(&self, fields: Fields<'_>) -> Option<Found> {
+        let desc = lower(fields.desc);
+        let category = lower(fields.category);
+
+        let mut score = 0;
+        let mut positions = Vec::new();
+        for term in &self.terms {
+            let in_category = text_match(term, &category).map(|(s, _)| s / 2);
+            let in_keys = fields.keys.iter().filter_map(|k| key_match(term, k)).max();
+            let elsewhere = in_category.max(in_keys);
+
+            match text_match(term, &desc) {
+                Some((s, hits)) if Some(s) >= elsewhere => {
+                    score += s;
+                    positions.extend(hits);
+                }
+                _ => score += elsewhere?,
+            }
+        }
+
+        Some(Found {
+            score,
+            desc_spans: spans(positions),
+        })
+    }
+}
+
+fn lower_char(c: char) -> char {
+    // one char for one, so indices into the original still line up
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn lower(s: &str) -> Vec<char> {
+    s.chars().map(lower_char).collect()
+}
+
+fn is_word_start(text: &[char], i: usize) -> bool {
+    i == 0 || !text[i - 1].is_alphanumeric()
+}
+
+fn is_word_end(text: &[char], end: usize) -> bool {
+    end == text.len() || !text[end].is_alphanumeric()
+}
+
+/// The best way `term` occurs in `text`, and the chars it covers.
+fn text_match(term: &[char], text: &[char]) -> Option<(u32, Vec<usize>)> {
+    if term.is_empty() || term.len() > text.len() {
+        return None;
+    }
+    substring(term, text)
+        .or_else(|| subsequence(term, text))
+        .or_else(|| typo(term, text))
+}
+
+fn substring(term: &[char], text: &[char]) -> Option<(u32, Vec<usize>)> {
+    let n = term.len();
+    (0..=text.len() - n)
+        .filter(|&i| text[i..i + n] == *term)
+        .map(|i| {
+            let score = match (is_word_start(text, i), is_word_end(text, i + n)) {
+                (true, true) => 100,
+                (true, false) => 80,
+                _ => 50,
+            };
+            (score, (i..i + n).collect())
+        })
+        // the first of the best
+        .fold(None, |best: Option<(u32, Vec<usize>)>, m| match &best {
+            Some((s, _)) if *s >= m.0 => best,
+            _ => Some(m),
+        })
+}
+
+/// The term's letters in order, starting where the fewest letters are
+/// skipped. Too many skipped and it's no match.
+fn subsequence(term: &[char], text: &[char]) -> Option<(u32, Vec<usize>)> {
+    if term.len() < 2 {
+        return None;
+    }
+
+    let mut best: Option<(u32, Vec<usize>)> = None;
+    for start in (0..text.len()).filter(|&i| text[i] == term[0]) {
+        let mut hits = vec![start];
+        let mut i = start + 1;
+        for &c in &term[1..] {
+            match text[i..].iter().position(|&t| t == c) {
+                Some(p) => {
+                    hits.push(i + p);
+                    i += p + 1;
+                }
+                None => break,
+            }
+        }
+        if hits.len() < term.len() {
+            // later starts can't do better
+            break;
+        }
+
+        let skipped = (i - start - term.len()) as u32;
+        let bonus: u32 = if is_word_start(text, start) { 10 } else { 0 };
+        let Some(score) = (40 + bonus).checked_sub(4 * skipped).filter(|&s| s > 0) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(s, _)| score > *s) {
+            best = Some((score, hits));
+        }
+    }
+    best
+}
+
+/// A word, or the start of one, within one edit of a longer term.
+fn typo(term: &[char], text: &[char]) -> Option<(u32, Vec<usize>)> {
+    if term.len() < 4 {
+        return None;
+    }
+
+    let words = (0..text.len()).filter(|&i| text[i].is_alphanumeric() && is_word_start(text, i));
+    for start in words {
+        let end = (start..text.len())
+            .find(|&i| is_word_end(text, i))
+            .unwrap_or(text.len());
+        let prefix_end = end.min(start + term.len());
+        for end in [end, prefix_end] {
+            if edit_distance(term, &text[start..end]) <= 1 {
+                return Some((20, (start..end).collect()));
+            }
+        }
+    }
+    None
+}
+
+/// Edits to turn `a` into `b`, swapping two neighbours counting as one.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// A term against one key label: all of it, or the start of a longer one.
+/// A whole label counts as a whole word, so the "p" of "ctrl shift p" finds
+/// Ctrl+Shift+P before a description starting with P.
+fn key_match(term: &[char], key: &str) -> Option<u32> {
+    let key: Vec<char> = key.chars().collect();
+    match key.starts_with(term) {
+        true if key.len() == term.len() => Some(100),
+        true if term.len() >= 2 => Some(40),
+        _ => None,
+    }
+}
+
+/// Sorted char indices as ranges of neighbours.
+fn spans(mut positions: Vec<usize>) -> Vec<Range<usize>> {
+    positions.sort_unstable();
+    positions.dedup();
+
+    let mut out: Vec<Range<usize>> = Vec::new();
+    for p in positions {
+        match out.last_mut() {
+            Some(r) if r.end == p => r.end += 1,
+            _ => out.push(p..p + 1),
+        }
+    }
+    out
+}
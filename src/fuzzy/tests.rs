@@ -0,0 +1,73 @@
+use super::{Fields, Found, Query};
+
+fn find(query: &str, desc: &str, keys: &[&str]) -> Option<Found> {
+    let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    Query::parse(query).find(Fields {
+        desc,
+        category: "General",
+        keys: &keys,
+    })
+}
+
+fn score(query: &str, desc: &str) -> u32 {
+    find(query, desc, &[]).unwrap().score
+}
+
+#[test]
+fn letters_and_words_combine() {
+    let found = find("sve as", "Save As…", &["ctrl", "shift", "s"]).unwrap();
+    assert_eq!(found.desc_spans, [0..1, 2..4, 5..7]);
+
+    // "as" isn't in order in "Save All"
+    assert_eq!(find("sve as", "Save All", &["ctrl", "shift", "l"]), None);
+}
+
+#[test]
+fn key_labels_match_whole() {
+    let palette = find("ctrl shift p", "Command Palette", &["ctrl", "shift", "p"]).unwrap();
+    // the "p" counts as the key, so nothing in the description lights up
+    assert!(palette.desc_spans.is_empty());
+    assert_eq!(palette.score, 300);
+
+    assert_eq!(find("ctrl shift p", "Print", &["ctrl", "p"]), None);
+}
+
+#[test]
+fn glyphs_read_as_labels() {
+    assert_eq!(
+        Query::parse("⌃⇧P").terms,
+        Query::parse("ctrl shift p").terms
+    );
+    assert_eq!(
+        Query::parse("Ctrl+Shift+P").terms,
+        Query::parse("ctrl shift p").terms
+    );
+}
+
+#[test]
+fn a_lone_plus_is_the_key() {
+    assert_eq!(Query::parse("+").terms, Query::parse("plus").terms);
+    assert_eq!(
+        Query::parse("ctrl++").terms,
+        Query::parse("ctrl plus").terms
+    );
+    assert!(find("+", "Zoom In", &["ctrl", "plus"]).is_some());
+    assert_eq!(find("ctrl +", "Zoom Out", &["ctrl", "minus"]), None);
+}
+
+#[test]
+fn typos_rank_last() {
+    let word = score("copy", "Copy Line");
+    let prefix = score("copy", "Copyright Notice");
+    let letters = score("copy", "Crop Your Image");
+    let typo = score("copy", "Cpoy Line");
+    assert!(word > prefix, "{word} > {prefix}");
+    assert!(prefix > letters, "{prefix} > {letters}");
+    assert!(letters > typo, "{letters} > {typo}");
+
+    let spans = find("cpoy", "Copy Line", &[]).unwrap().desc_spans;
+    // the whole misspelt word
+    assert_eq!(spans.first(), Some(&(0..4)));
+    assert_eq!(spans.len(), 1);
+    assert_eq!(find("copy", "Cut", &[]), None);
+}
@@ -0,0 +1,143 @@
+//! Importers read an app's own keybinding config into the entry model of the
+//! bundled packs, so a pack shows what is actually bound rather than the
+//! app's defaults.
+//!
+//! Each importer names the pack it belongs to; the resolver merges its output
+//! over that pack and watches the files it reads.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::shortcut_overlay::{Overlay, OverlayEntry, Selector};
+use crate::shortcut_pack::{Diagnostic, ShortcutPack};
+use crate::shortcut_resolver::Layer;
+
+mod cosmic;
+mod gimp;
+mod obsidian;
+mod tmux;
+mod vscode;
+
+#[cfg(test)]
+mod tests;
+
+pub use cosmic::Cosmic;
+pub use gimp::Gimp;
+pub use obsidian::Obsidian;
+pub use tmux::Tmux;
+pub use vscode::VsCode;
+
+/// Turns an app's config files into packs or overlays for one pack.
+pub trait Importer: fmt::Debug + Send + Sync {
+    /// The app_id of the pack the result is merged over.
+    fn app_id(&self) -> &str;
+
+    /// Files it reads, whether they exist or not; the shortcut watcher
+    /// observes their directories.
+    fn files(&self) -> Vec<PathBuf>;
+
+    /// Read the config. Empty when the app isn't set up here.
+    fn import(&self) -> Vec<Imported>;
+}
+
+/// The importers the app runs with.
+pub fn all() -> Vec<Arc<dyn Importer>> {
+    vec![
+        Arc::new(Cosmic),
+        Arc::new(VsCode),
+        Arc::new(Obsidian),
+        Arc::new(Gimp),
+        Arc::new(Tmux),
+    ]
+}
+
+/// What an importer made of one config file.
+#[derive(Debug)]
+pub struct Imported {
+    pub layer: Layer,
+    pub path: PathBuf,
+    /// `None` if the file couldn't be read or parsed; see `diagnostics`.
+    pub content: Option<ImportedContent>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug)]
+pub enum ImportedContent {
+    /// A pack; a base (not `extends`) one replaces the hand-written base files.
+    Pack(ShortcutPack),
+    /// Changes to the merged pack, applied like a `.user.json` overlay.
+    Overlay(Overlay),
+}
+
+/// `$HOME`.
+fn home() -> Option<PathBuf> {
+    std::env::var("HOME").ok().map(PathBuf::from)
+}
+
+/// `$XDG_CONFIG_HOME`, or `~/.config`.
+fn config_home() -> Option<PathBuf> {
+    std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home().map(|h| h.join(".config")))
+}
+
+/// Where a Flatpak app keeps its config: `~/.var/app/<id>/config`.
+fn flatpak_config(app: &str) -> Option<PathBuf> {
+    home().map(|h| h.join(".var/app").join(app).join("config"))
+}
+
+/// Bind `command` to exactly `keys`, as apps do when the user sets its
+/// shortcuts: its bundled entries go, and an empty `keys` leaves it unbound.
+fn set_bindings(overlay: &mut Overlay, command: &str, keys: Vec<String>) {
+    overlay
+        .add
+        .retain(|a| a.command.as_deref() != Some(command));
+    overlay.hide.push(Selector {
+        keys: None,
+        desc: None,
+        command: Some(command.to_string()),
+    });
+    overlay
+        .add
+        .extend(keys.into_iter().map(|keys| OverlayEntry {
+            keys,
+            desc: String::new(),
+            category: None,
+            command: Some(command.to_string()),
+        }));
+}
+
+/// xkb / GDK keysym name -> OrbitKeys key name. `None` for media and
+/// hardware keys, which have nothing worth learning.
+fn keysym_label(keysym: &str) -> Option<String> {
+    if keysym.starts_with("XF86") {
+        return None;
+    }
+
+    let label = match keysym.to_ascii_lowercase().as_str() {
+        "return" => "Enter",
+        "escape" => "Esc",
+        "page_up" | "prior" => "PageUp",
+        "page_down" | "next" => "PageDown",
+        "slash" => "/",
+        "backslash" => "\\",
+        "period" => ".",
+        "comma" => ",",
+        "semicolon" => ";",
+        "apostrophe" => "'",
+        "grave" => "`",
+        "bracketleft" => "[",
+        "bracketright" => "]",
+        "equal" => "=",
+        "minus" | "kp_subtract" => "Minus",
+        "plus" | "kp_add" => "Plus",
+        "space" => "Space",
+        // letters are written uppercase in packs
+        _ if keysym.len() == 1 => return Some(keysym.to_ascii_uppercase()),
+        _ => keysym,
+    };
+    Some(label.to_string())
+}
@@ -0,0 +1,751 @@
+//! The compositor's own keybindings, read from the COSMIC settings config so
+//! the desktop pack matches what is actually bound.
+//!
+//! COSMIC keeps the shipped bindings in
+//! `$XDG_DATA_DIRS/cosmic/com.system76.CosmicSettings.Shortcuts/v1/defaults`
+//! and the user's changes in `~/.config/cosmic/…/v1/custom`. Both are RON maps
+//! from a binding to an action:
+//!
+//! ```ron
+//! {
+//!     (modifiers: [Super], key: "q"): Close,
+//!     (modifiers: [Super, Shift], key: "Left"): Move(Left),
+//!     (modifiers: [Super], key: "t", description: Some("Terminal")): Spawn("cosmic-term"),
+//!     (modifiers: [Super], key: "y"): Disable,
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{config_home, keysym_label, Imported, ImportedContent, Importer};
+use crate::key_format::KeySequence;
+use crate::shortcut_pack::{Diagnostic, Layout, Severity, Shortcut, ShortcutPack};
+use crate::shortcut_resolver::{Layer, DESKTOP_APP_ID};
+
+const CONFIG_ID: &str = "com.system76.CosmicSettings.Shortcuts";
+const DEFAULTS: &str = "defaults";
+const CUSTOM: &str = "custom";
+
+/// Category order of the generated pack, as in `shortcuts/cosmic-desktop.json`.
+const CATEGORIES: &[&str] = &[
+    "Launcher",
+    "Navigation",
+    "Movement",
+    "Windows",
+    "Tiling",
+    "System",
+    "Apps",
+    "Accessibility",
+    "Other",
+];
+
+/// Where cosmic-config keeps `key` under a config or data dir.
+fn config_file(base: &Path, key: &str) -> PathBuf {
+    base.join("cosmic").join(CONFIG_ID).join("v1").join(key)
+}
+
+/// `$XDG_CONFIG_HOME/cosmic/<id>/v1/custom`.
+fn custom_path() -> Option<PathBuf> {
+    Some(config_file(&config_home()?, CUSTOM))
+}
+
+/// The first `$XDG_DATA_DIRS/cosmic/<id>/v1/defaults` that exists.
+fn defaults_path() -> Option<PathBuf> {
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    std::env::split_paths(&data_dirs)
+        .map(|d| config_file(&d, DEFAULTS))
+        .find(|p| p.is_file())
+}
+
+/// The compositor's keybindings, for the desktop pack.
+#[derive(Debug)]
+pub struct Cosmic;
+
+impl Importer for Cosmic {
+    fn app_id(&self) -> &str {
+        DESKTOP_APP_ID
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        [defaults_path(), custom_path()]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// The installed defaults and the user's custom bindings. Empty when
+    /// neither exists (not on COSMIC).
+    fn import(&self) -> Vec<Imported> {
+        let custom = custom_path().filter(|p| p.is_file());
+        import_files(defaults_path().as_deref(), custom.as_deref())
+    }
+}
+
+/// Turn the two files into packs: the defaults (`System`), minus every
+/// binding the user changed or disabled, replace the hand-written desktop
+/// pack; the custom bindings (`User`) extend it.
+pub fn import_files(defaults: Option<&Path>, custom: Option<&Path>) -> Vec<Imported> {
+    let custom = custom.map(|p| (p, read_bindings(p)));
+    let overridden: HashSet<(Vec<String>, Option<String>)> = custom
+        .iter()
+        .flat_map(|(_, r)| r.as_ref().ok())
+        .flatten()
+        .map(|(b, _)| b.id())
+        .collect();
+
+    let mut out = Vec::new();
+
+    if let Some(path) = defaults {
+        let keep = |b: &Binding| !overridden.contains(&b.id());
+        out.push(to_imported(
+            Layer::System,
+            path,
+            read_bindings(path),
+            false,
+            keep,
+        ));
+    }
+    if let Some((path, bindings)) = custom {
+        out.push(to_imported(Layer::User, path, bindings, true, |_| true));
+    }
+
+    out
+}
+
+fn to_imported(
+    layer: Layer,
+    path: &Path,
+    bindings: Result<Vec<(Binding, Action)>, String>,
+    extends: bool,
+    keep: impl Fn(&Binding) -> bool,
+) -> Imported {
+    let diag = |severity, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line: None,
+        severity,
+        message,
+    };
+
+    let bindings = match bindings {
+        Ok(b) => b,
+        Err(e) => {
+            return Imported {
+                layer,
+                path: path.to_path_buf(),
+                content: None,
+                diagnostics: vec![diag(Severity::Error, e)],
+            }
+        }
+    };
+
+    let bindings: Vec<_> = bindings
+        .into_iter()
+        .filter(|(b, a)| keep(b) && a.name != "Disable")
+        .collect();
+    let (shortcuts, skipped) = to_shortcuts(&bindings);
+    let diagnostics = skipped
+        .into_iter()
+        .map(|k| diag(Severity::Warning, format!("binding \"{k}\" can't be shown")))
+        .collect();
+
+    Imported {
+        layer,
+        path: path.to_path_buf(),
+        content: Some(ImportedContent::Pack(ShortcutPack {
+            name: Some("COSMIC Desktop".into()),
+            extends,
+            categories: CATEGORIES.iter().map(|c| c.to_string()).collect(),
+            shortcuts,
+            ..Default::default()
+        })),
+        diagnostics,
+    }
+}
+
+// ---------- Bindings ----------
+
+/// The left side of a config entry.
+#[derive(Debug, Clone, PartialEq)]
+struct Binding {
+    modifiers: Vec<String>,
+    /// xkb keysym name; `None` for a bare modifier (Super opens the launcher).
+    key: Option<String>,
+    description: Option<String>,
+}
+
+impl Binding {
+    /// What identifies the binding, regardless of modifier order.
+    fn id(&self) -> (Vec<String>, Option<String>) {
+        let mut mods = self.modifiers.clone();
+        mods.sort();
+        (mods, self.key.clone())
+    }
+
+    /// OrbitKeys key string, e.g. "Super+Shift+Left". `None` for media keys
+    /// and the like, which have nothing worth learning.
+    fn keys(&self, key: Option<&str>) -> Option<String> {
+        let mut parts = self.modifiers.clone();
+        match key {
+            Some(k) => parts.push(keysym_label(k)?),
+            None if parts.len() == 1 => {}
+            None => return None,
+        }
+        Some(parts.join("+"))
+    }
+}
+
+/// The right side: `Close`, `Focus(Left)`, `Workspace(3)`, `Spawn("…")`.
+#[derive(Debug, Clone, PartialEq)]
+struct Action {
+    name: String,
+    arg: Option<String>,
+}
+
+fn read_bindings(path: &Path) -> Result<Vec<(Binding, Action)>, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse_bindings(&raw).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn parse_bindings(raw: &str) -> Result<Vec<(Binding, Action)>, String> {
+    let Value::Map(entries) = Parser::new(raw).document()? else {
+        return Err("expected a map of bindings".into());
+    };
+
+    entries
+        .into_iter()
+        .map(|(k, v)| Ok((binding(k)?, action(v)?)))
+        .collect()
+}
+
+fn binding(v: Value) -> Result<Binding, String> {
+    let Value::Struct(_, fields) = v else {
+        return Err(format!("expected a binding, found {v:?}"));
+    };
+
+    let mut b = Binding {
+        modifiers: Vec::new(),
+        key: None,
+        description: None,
+    };
+    for (name, value) in fields {
+        match (name.as_str(), value.unwrap_some()) {
+            ("modifiers", Value::List(mods)) => {
+                for m in mods {
+                    let Value::Ident(m) = m else { continue };
+                    b.modifiers.push(m);
+                }
+            }
+            ("key", Value::Str(k)) => b.key = Some(k),
+            ("description", Value::Str(d)) => b.description = Some(d),
+            _ => {}
+        }
+    }
+
+    // legend order, like the hand-written packs
+    let rank = |m: &String| {
+        ["Super", "Ctrl", "Alt", "Shift"]
+            .iter()
+            .position(|x| x == m)
+            .unwrap_or(4)
+    };
+    b.modifiers.sort_by_key(rank);
+    Ok(b)
+}
+
+fn action(v: Value) -> Result<Action, String> {
+    match v {
+        Value::Ident(name) => Ok(Action { name, arg: None }),
+        Value::Tuple(Some(name), args) => {
+            let arg = args.into_iter().next().and_then(|a| match a {
+                Value::Ident(s) | Value::Str(s) | Value::Num(s) => Some(s),
+                _ => None,
+            });
+            Ok(Action { name, arg })
+        }
+        other => Err(format!("expected an action, found {other:?}")),
+    }
+}
+
+// ---------- Actions -> shortcuts ----------
+
+/// Description and category of an action, and the layout it only makes
+/// sense on, if any.
+fn describe(a: &Action, b: &Binding) -> (String, &'static str, Option<Layout>) {
+    let arg = a.arg.as_deref().unwrap_or("");
+    let dir = arg.to_lowercase();
+    let tiling = Some(Layout::Tiling);
+
+    let (desc, category, layout) = match (a.name.as_str(), arg) {
+        ("Close", _) => ("Close".into(), "Windows", None),
+        ("Maximize", _) => ("Maximize".into(), "Windows", None),
+        ("Minimize", _) => ("Minimize".into(), "Windows", None),
+        ("Fullscreen", _) => ("Fullscreen".into(), "Windows", None),
+        ("Resizing", "Inwards") => ("Resize mode (shrink)".into(), "Windows", None),
+        ("Resizing", _) => ("Resize mode".into(), "Windows", None),
+        ("ToggleStacking", _) => ("Stack windows".into(), "Windows", None),
+        ("ToggleSticky", _) => ("Sticky window".into(), "Windows", None),
+
+        // one entry per layout, see `per_layout`
+        ("ToggleTiling", _) => ("Toggle tiling".into(), "Tiling", None),
+        ("ToggleWindowFloating", _) => ("Floating toggle".into(), "Tiling", tiling),
+        ("ToggleOrientation" | "Orientation", _) => ("Orientation toggle".into(), "Tiling", tiling),
+        ("SwapWindow", _) => ("Swap window".into(), "Tiling", tiling),
+        ("Focus", "Out") => ("Select tree up".into(), "Tiling", tiling),
+        ("Focus", "In") => ("Select tree down".into(), "Tiling", tiling),
+
+        ("Focus", _) => (format!("Focus {dir}"), "Navigation", None),
+        ("Workspace", n) => (format!("Workspace {n}"), "Navigation", None),
+        ("NextWorkspace", _) => ("Next workspace".into(), "Navigation", None),
+        ("PreviousWorkspace", _) => ("Previous workspace".into(), "Navigation", None),
+        ("LastWorkspace", _) => ("Last workspace".into(), "Navigation", None),
+        ("SwitchOutput", _) => (format!("Switch display {dir}"), "Navigation", None),
+        ("NextOutput", _) => ("Next display".into(), "Navigation", None),
+        ("PreviousOutput", _) => ("Previous display".into(), "Navigation", None),
+
+        ("Move", _) => (format!("Move window {dir}"), "Movement", None),
+        ("MoveToWorkspace", n) => (format!("Move to workspace {n}"), "Movement", None),
+        ("SendToWorkspace", n) => (format!("Send to workspace {n}"), "Movement", None),
+        ("MoveToNextWorkspace", _) => ("Move to next workspace".into(), "Movement", None),
+        ("MoveToPreviousWorkspace", _) => ("Move to previous workspace".into(), "Movement", None),
+        ("MoveToLastWorkspace", _) => ("Move to last workspace".into(), "Movement", None),
+        ("MoveToOutput", _) => (format!("Move to display {dir}"), "Movement", None),
+        ("MoveToNextOutput", _) => ("Move to next display".into(), "Movement", None),
+        ("MoveToPreviousOutput", _) => ("Move to previous display".into(), "Movement", None),
+
+        ("ZoomIn", _) => ("Zoom in".into(), "Accessibility", None),
+        ("ZoomOut", _) => ("Zoom out".into(), "Accessibility", None),
+
+        ("Spawn", cmd) => {
+            let desc = b
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Run {cmd}"));
+            (desc, "Apps", None)
+        }
+        ("System", action) => {
+            let (desc, category) = system_action(action);
+            (desc, category, None)
+        }
+
+        (name, "") => (humanize(name), "Other", None),
+        (name, arg) => (
+            format!("{} {}", humanize(name), arg.to_lowercase()),
+            "Other",
+            None,
+        ),
+    };
+
+    // A custom description always wins.
+    let desc = b.description.clone().unwrap_or(desc);
+    (desc, category, layout)
+}
+
+/// The entries for one binding. Toggling tiling reads differently on each
+/// layout, so it gets one per layout, unless the user described it.
+fn per_layout(
+    a: &Action,
+    b: &Binding,
+    desc: String,
+    layout: Option<Layout>,
+) -> Vec<(String, Option<Layout>)> {
+    match a.name.as_str() {
+        "ToggleTiling" if b.description.is_none() => vec![
+            ("Enable tiling".into(), Some(Layout::Floating)),
+            ("Disable tiling".into(), Some(Layout::Tiling)),
+        ],
+        _ => vec![(desc, layout)],
+    }
+}
+
+fn system_action(action: &str) -> (String, &'static str) {
+    let (desc, category) = match action {
+        "Launcher" => ("Launcher", "Launcher"),
+        "AppLibrary" => ("Apps library", "System"),
+        "WorkspaceOverview" => ("Workspaces overview", "System"),
+        "WindowSwitcher" => ("Switch window", "Navigation"),
+        "WindowSwitcherPrevious" => ("Switch window (reverse)", "Navigation"),
+        "HomeFolder" => ("Files", "System"),
+        "Terminal" => ("Terminal", "System"),
+        "WebBrowser" => ("Web browser", "System"),
+        "LockScreen" => ("Lock screen", "System"),
+        "LogOut" => ("Log out", "System"),
+        "Screenshot" => ("Screenshot", "System"),
+        "InputSourceSwitch" => ("Switch input", "System"),
+        "ScreenReader" => ("Screen reader", "Accessibility"),
+        other => return (humanize(other), "System"),
+    };
+    (desc.to_string(), category)
+}
+
+/// "ToggleSticky" -> "Toggle sticky".
+fn humanize(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push(' ');
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Bindings of one action that together fill a key group, e.g. Focus on all
+/// four arrows, shown as one "Super+Arrows" entry.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Group {
+    Arrows,
+    Vim,
+    Digits,
+}
+
+impl Group {
+    /// The group a binding belongs to and its place in it.
+    fn of(b: &Binding, a: &Action) -> Option<(Group, String)> {
+        let key = b.key.as_deref()?;
+        let arg = a.arg.as_deref()?;
+
+        let directional = matches!(
+            a.name.as_str(),
+            "Focus" | "Move" | "SwitchOutput" | "MoveToOutput"
+        );
+        if directional {
+            if key == arg && ["Left", "Right", "Up", "Down"].contains(&key) {
+                return Some((Group::Arrows, arg.to_string()));
+            }
+            let vim = match key {
+                "h" => "Left",
+                "j" => "Down",
+                "k" => "Up",
+                "l" => "Right",
+                _ => return None,
+            };
+            return (vim == arg).then(|| (Group::Vim, arg.to_string()));
+        }
+
+        let numbered = matches!(
+            a.name.as_str(),
+            "Workspace" | "MoveToWorkspace" | "SendToWorkspace"
+        );
+        let digit = matches!(key.as_bytes(), [b'1'..=b'9']);
+        (numbered && digit && key == arg).then(|| (Group::Digits, arg.to_string()))
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Group::Arrows | Group::Vim => 4,
+            Group::Digits => 9,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Group::Arrows => "Arrows",
+            Group::Vim => "HJKL",
+            Group::Digits => "1-9",
+        }
+    }
+
+    fn describe(self, a: &Action) -> String {
+        let base = match a.name.as_str() {
+            "Focus" => "Focus neighbor",
+            "Move" => "Move window",
+            "SwitchOutput" => "Switch display",
+            "MoveToOutput" => "Move to display",
+            "Workspace" => "Workspace 1-9",
+            "MoveToWorkspace" => "Move to workspace 1-9",
+            "SendToWorkspace" => "Send to workspace 1-9",
+            other => other,
+        };
+        match self {
+            Group::Vim => format!("{base} (Vim)"),
+            _ => base.to_string(),
+        }
+    }
+}
+
+/// Convert bindings in file order, folding complete key groups. Also returns
+/// the bindings whose keys can't be displayed.
+fn to_shortcuts(bindings: &[(Binding, Action)]) -> (Vec<Shortcut>, Vec<String>) {
+    type GroupId = (Vec<String>, String, Group);
+
+    let grouped: Vec<Option<(GroupId, String)>> = bindings
+        .iter()
+        .map(|(b, a)| {
+            let (group, member) = Group::of(b, a)?;
+            Some(((b.modifiers.clone(), a.name.clone(), group), member))
+        })
+        .collect();
+
+    let mut members: HashMap<&GroupId, HashSet<&str>> = HashMap::new();
+    for (id, member) in grouped.iter().flatten() {
+        members.entry(id).or_default().insert(member);
+    }
+
+    let mut shortcuts = Vec::new();
+    let mut skipped = Vec::new();
+    let mut emitted: HashSet<&GroupId> = HashSet::new();
+
+    for ((b, a), group) in bindings.iter().zip(&grouped) {
+        let (_, category, layout) = describe(a, b);
+
+        let folded = group
+            .as_ref()
+            .filter(|(id, _)| members[id].len() == id.2.size());
+        let (keys, desc) = match folded {
+            Some((id, _)) => {
+                if !emitted.insert(id) {
+                    continue;
+                }
+                (b.keys(Some(id.2.key())), id.2.describe(a))
+            }
+            None => (b.keys(b.key.as_deref()), describe(a, b).0),
+        };
+
+        match keys.filter(|k| KeySequence::parse(k).is_ok()) {
+            Some(keys) => {
+                for (desc, layout) in per_layout(a, b, desc, layout) {
+                    shortcuts.push(Shortcut {
+                        keys: keys.clone(),
+                        desc,
+                        category: Some(category.to_string()),
+                        tiling_only: layout == Some(Layout::Tiling),
+                        floating_only: layout == Some(Layout::Floating),
+                        command: None,
+                    });
+                }
+            }
+            None => {
+                let mut raw = b.modifiers.clone();
+                raw.extend(b.key.clone());
+                // Media and hardware keys are left out on purpose.
+                if !b.key.as_deref().is_some_and(|k| k.starts_with("XF86")) {
+                    skipped.push(raw.join("+"));
+                }
+            }
+        }
+    }
+
+    (shortcuts, skipped)
+}
+
+// ---------- RON ----------
+
+/// The subset of RON the config uses. Struct and variant names are kept,
+/// unlike in a generic RON value, since the action is the variant name.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    /// `Close`, `Left`, `None`, `true`
+    Ident(String),
+    Str(String),
+    Num(String),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// `(a, b)` or `Name(a, b)`
+    Tuple(Option<String>, Vec<Value>),
+    /// `(field: a)` or `Name(field: a)`
+    Struct(Option<String>, Vec<(String, Value)>),
+}
+
+impl Value {
+    /// `Some(x)` -> `x`
+    fn unwrap_some(self) -> Value {
+        match self {
+            Value::Tuple(Some(name), mut args) if name == "Some" && args.len() == 1 => {
+                args.remove(0)
+            }
+            v => v,
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn document(&mut self) -> Result<Value, String> {
+        let v = self.value()?;
+        self.skip_ws();
+        if self.pos < self.src.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(v)
+    }
+
+    fn error(&self, msg: &str) -> String {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+        format!("line {line}: {msg}")
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Whitespace and `//` / `/* */` comments.
+    fn skip_ws(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(body) = trimmed.strip_prefix("/*") {
+                self.pos += body.find("*/").map_or(trimmed.len(), |i| i + 4);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    /// Items up to `close`, with an optional trailing comma.
+    fn items<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let mut out = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Ok(out);
+            }
+            out.push(item(self)?);
+            if !self.eat(',') {
+                self.expect(close)?;
+                return Ok(out);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let entries = self.items('}', |p| {
+                    let k = p.value()?;
+                    p.expect(':')?;
+                    Ok((k, p.value()?))
+                })?;
+                Ok(Value::Map(entries))
+            }
+            Some('[') => {
+                self.pos += 1;
+                Ok(Value::List(self.items(']', Self::value)?))
+            }
+            Some('(') => self.parens(None),
+            Some('"') => self.string().map(Value::Str),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let len = self
+                    .rest()
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_')))
+                    .unwrap_or(self.rest().len());
+                let num = self.rest()[..len].to_string();
+                self.pos += len;
+                Ok(Value::Num(num))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.ident();
+                self.skip_ws();
+                if self.peek() == Some('(') {
+                    self.parens(Some(name))
+                } else {
+                    Ok(Value::Ident(name))
+                }
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest().len());
+        let name = self.rest()[..len].to_string();
+        self.pos += len;
+        name
+    }
+
+    /// A tuple or struct body; it is a struct if it starts with `field:`.
+    fn parens(&mut self, name: Option<String>) -> Result<Value, String> {
+        self.expect('(')?;
+        self.skip_ws();
+
+        let start = self.pos;
+        let is_struct = {
+            let field = self.ident();
+            !field.is_empty() && self.eat(':')
+        };
+        self.pos = start;
+
+        if is_struct {
+            let fields = self.items(')', |p| {
+                p.skip_ws();
+                let field = p.ident();
+                p.expect(':')?;
+                Ok((field, p.value()?))
+            })?;
+            Ok(Value::Struct(name, fields))
+        } else {
+            Ok(Value::Tuple(name, self.items(')', Self::value)?))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(c) => out.push(c),
+                    None => break,
+                },
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
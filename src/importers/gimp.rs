@@ -0,0 +1,162 @@
+//! GIMP's shortcuts. GIMP 3 writes the actions the user changed to
+//! `~/.config/GIMP/3.0/shortcutsrc`, each with its full set of accelerators
+//! (none unbinds it):
+//!
+//! ```text
+//! (action "file-open" "<Primary>o" "F3")
+//! (action "tools-paintbrush")
+//! ```
+//!
+//! GIMP 2.10 keeps every action in `~/.config/GIMP/2.10/menurc`, with the
+//! unchanged ones commented out:
+//!
+//! ```text
+//! ; (gtk_accel_path "<Actions>/file/file-new" "<Primary>n")
+//! (gtk_accel_path "<Actions>/tools/tools-paintbrush" "<Shift>p")
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{
+    config_home, flatpak_config, keysym_label, set_bindings, Imported, ImportedContent, Importer,
+};
+use crate::key_format::KeySequence;
+use crate::shortcut_overlay::Overlay;
+use crate::shortcut_pack::{Diagnostic, Severity};
+use crate::shortcut_resolver::Layer;
+
+/// Config files by GIMP version, newest first.
+const FILES: &[&str] = &["3.0/shortcutsrc", "2.10/menurc"];
+
+/// GIMP's shortcuts, for the `gimp` pack.
+#[derive(Debug)]
+pub struct Gimp;
+
+impl Importer for Gimp {
+    fn app_id(&self) -> &str {
+        "gimp"
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        let dirs = [config_home(), flatpak_config("org.gimp.GIMP")];
+        dirs.into_iter()
+            .flatten()
+            .flat_map(|d| FILES.iter().map(move |f| d.join("GIMP").join(f)))
+            .collect()
+    }
+
+    /// The newest version's file that exists.
+    fn import(&self) -> Vec<Imported> {
+        let files = self.files();
+        let newest = FILES
+            .iter()
+            .find_map(|f| files.iter().find(|p| p.ends_with(f) && p.is_file()));
+        newest.map(|p| vec![import_file(p)]).unwrap_or_default()
+    }
+}
+
+/// Turn a `shortcutsrc` or `menurc` into an overlay for the `gimp` pack.
+pub fn import_file(path: &Path) -> Imported {
+    let diag = |severity, line: Option<usize>, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line,
+        severity,
+        message,
+    };
+
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            return Imported {
+                layer: Layer::User,
+                path: path.to_path_buf(),
+                content: None,
+                diagnostics: vec![diag(Severity::Error, None, e.to_string())],
+            }
+        }
+    };
+
+    let mut overlay = Overlay::default();
+    let mut diagnostics = Vec::new();
+
+    for (n, line) in raw.lines().enumerate() {
+        let Some((action, accels)) = parse_line(line) else {
+            continue;
+        };
+
+        let mut keys = Vec::new();
+        for accel in accels.iter().filter(|a| !a.is_empty()) {
+            match accel_keys(accel) {
+                Some(k) => keys.push(k),
+                None => diagnostics.push(diag(
+                    Severity::Warning,
+                    Some(n + 1),
+                    format!("shortcut \"{accel}\" of {action} can't be shown"),
+                )),
+            }
+        }
+        set_bindings(&mut overlay, &action, keys);
+    }
+
+    Imported {
+        layer: Layer::User,
+        path: path.to_path_buf(),
+        content: Some(ImportedContent::Overlay(overlay)),
+        diagnostics,
+    }
+}
+
+/// The action and its accelerators, for an `(action …)` or an uncommented
+/// `(gtk_accel_path …)` line.
+fn parse_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    let (form, rest) = line.strip_prefix('(')?.split_once(' ')?;
+    let mut strings = quoted(rest).into_iter();
+
+    let action = match form {
+        "action" => strings.next()?,
+        // "<Actions>/file/file-open" -> "file-open"
+        "gtk_accel_path" => strings.next()?.rsplit('/').next()?.to_string(),
+        _ => return None,
+    };
+    Some((action, strings.collect()))
+}
+
+/// The double-quoted strings in `s`, unescaped.
+fn quoted(s: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut chars = s.chars();
+    while chars.any(|c| c == '"') {
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next()),
+                '"' => break,
+                c => value.push(c),
+            }
+        }
+        out.push(value);
+    }
+    out
+}
+
+/// "<Primary><Shift>e" -> "Ctrl+Shift+E". `None` for keys without a label.
+fn accel_keys(accel: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut rest = accel;
+    while let Some((modifier, tail)) = rest.strip_prefix('<').and_then(|r| r.split_once('>')) {
+        parts.push(match modifier.to_ascii_lowercase().as_str() {
+            "primary" | "control" | "ctrl" => "Ctrl",
+            "shift" => "Shift",
+            "alt" | "mod1" => "Alt",
+            "super" | "meta" | "mod4" => "Super",
+            _ => return None,
+        });
+        rest = tail;
+    }
+
+    let mut raw: String = parts.iter().map(|p| format!("{p}+")).collect();
+    raw.push_str(&keysym_label(rest)?);
+    KeySequence::parse(&raw).ok().map(|k| k.to_string())
+}
@@ -0,0 +1,166 @@
+//! Obsidian's hotkeys, from the `.obsidian/hotkeys.json` of the vault opened
+//! last. Only changed commands are listed; each maps to its full set of
+//! hotkeys, and an empty list unbinds it:
+//!
+//! ```json
+//! {
+//!   "editor:toggle-bold": [{ "modifiers": ["Mod", "Shift"], "key": "B" }],
+//!   "graph:open": []
+//! }
+//! ```
+//!
+//! The vaults are listed in `~/.config/obsidian/obsidian.json`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{config_home, flatpak_config, set_bindings, Imported, ImportedContent, Importer};
+use crate::key_format::KeySequence;
+use crate::shortcut_overlay::Overlay;
+use crate::shortcut_pack::{line_of, Diagnostic, Severity};
+use crate::shortcut_resolver::Layer;
+
+#[derive(Debug, Deserialize)]
+struct Hotkey {
+    #[serde(default)]
+    modifiers: Vec<String>,
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vaults {
+    #[serde(default)]
+    vaults: BTreeMap<String, Vault>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vault {
+    path: PathBuf,
+    /// Last opened, in milliseconds.
+    #[serde(default)]
+    ts: u64,
+    #[serde(default)]
+    open: bool,
+}
+
+/// Obsidian's hotkeys, for the `obsidian` pack.
+#[derive(Debug)]
+pub struct Obsidian;
+
+impl Importer for Obsidian {
+    fn app_id(&self) -> &str {
+        "obsidian"
+    }
+
+    /// Both `obsidian.json` candidates and the current vault's hotkeys.
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = vault_lists();
+        files.extend(hotkeys_path());
+        files
+    }
+
+    fn import(&self) -> Vec<Imported> {
+        hotkeys_path()
+            .filter(|p| p.is_file())
+            .map(|p| vec![import_file(&p)])
+            .unwrap_or_default()
+    }
+}
+
+/// `obsidian.json` of the native and the Flatpak build.
+fn vault_lists() -> Vec<PathBuf> {
+    [config_home(), flatpak_config("md.obsidian.Obsidian")]
+        .into_iter()
+        .flatten()
+        .map(|d| d.join("obsidian").join("obsidian.json"))
+        .collect()
+}
+
+/// The hotkeys file of the open vault, or else of the one opened last.
+fn hotkeys_path() -> Option<PathBuf> {
+    let list = vault_lists().into_iter().find(|p| p.is_file())?;
+    let raw = fs::read_to_string(list).ok()?;
+    let vaults: Vaults = serde_json::from_str(&raw).ok()?;
+    let vault = vaults.vaults.into_values().max_by_key(|v| (v.open, v.ts))?;
+    Some(vault.path.join(".obsidian").join("hotkeys.json"))
+}
+
+/// Turn one `hotkeys.json` into an overlay for the `obsidian` pack.
+pub fn import_file(path: &Path) -> Imported {
+    let diag = |severity, line: Option<usize>, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line,
+        severity,
+        message,
+    };
+    let failed = |d: Diagnostic| Imported {
+        layer: Layer::User,
+        path: path.to_path_buf(),
+        content: None,
+        diagnostics: vec![d],
+    };
+
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => return failed(diag(Severity::Error, None, e.to_string())),
+    };
+    let commands: BTreeMap<String, Vec<Hotkey>> = match serde_json::from_str(&raw) {
+        Ok(c) => c,
+        Err(e) => return failed(diag(Severity::Error, Some(e.line()), e.to_string())),
+    };
+
+    let mut overlay = Overlay::default();
+    let mut diagnostics = Vec::new();
+
+    for (command, hotkeys) in commands {
+        let mut keys = Vec::new();
+        for h in hotkeys {
+            match hotkey_keys(&h) {
+                Ok(k) => keys.push(k),
+                Err(e) => diagnostics.push(diag(
+                    Severity::Warning,
+                    line_of(&raw, &command),
+                    format!("hotkey for \"{command}\" can't be shown: {e}"),
+                )),
+            }
+        }
+        set_bindings(&mut overlay, &command, keys);
+    }
+
+    Imported {
+        layer: Layer::User,
+        path: path.to_path_buf(),
+        content: Some(ImportedContent::Overlay(overlay)),
+        diagnostics,
+    }
+}
+
+/// `{ "modifiers": ["Mod", "Shift"], "key": "ArrowLeft" }` -> "Ctrl+Shift+Left".
+fn hotkey_keys(h: &Hotkey) -> Result<String, String> {
+    let mut parts: Vec<&str> = h
+        .modifiers
+        .iter()
+        .map(|m| match m.as_str() {
+            // Mod is Cmd on macOS, Ctrl elsewhere
+            "Mod" => "Ctrl",
+            "Meta" => "Super",
+            other => other,
+        })
+        .collect();
+
+    let key = h.key.strip_prefix("Arrow").unwrap_or(&h.key);
+    parts.push(match key {
+        " " => "Space",
+        "+" => "Plus",
+        "-" => "Minus",
+        k => k,
+    });
+
+    let raw = parts.join("+");
+    KeySequence::parse(&raw)
+        .map(|k| k.to_string())
+        .map_err(|e| format!("\"{raw}\": {e}"))
+}
@@ -0,0 +1,353 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::{cosmic, gimp, obsidian, tmux, vscode, Imported, Importer};
+use crate::shortcut_pack::{Diagnostic, Layout, Severity};
+use crate::shortcut_resolver::{Layer, LoadedEntry, ShortcutResolver, ShortcutRoot};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/importers/fixtures")
+        .join(name)
+}
+
+/// Imports fixture files instead of the user's config.
+#[derive(Debug)]
+struct Fixture {
+    app_id: &'static str,
+    files: Vec<PathBuf>,
+    import: fn(&[PathBuf]) -> Vec<Imported>,
+}
+
+impl Importer for Fixture {
+    fn app_id(&self) -> &str {
+        self.app_id
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        self.files.clone()
+    }
+
+    fn import(&self) -> Vec<Imported> {
+        (self.import)(&self.files)
+    }
+}
+
+/// A resolver over the bundled packs with `importer` registered.
+fn resolver(importer: Fixture) -> ShortcutResolver {
+    let bundled = ShortcutRoot {
+        layer: Layer::System,
+        dir: Path::new(env!("CARGO_MANIFEST_DIR")).join("shortcuts"),
+    };
+    ShortcutResolver::new(vec![bundled], vec![Arc::new(importer)]).unwrap()
+}
+
+/// A resolver importing the single fixture `file` for `app_id`.
+fn one_file(
+    app_id: &'static str,
+    file: &str,
+    import: fn(&[PathBuf]) -> Vec<Imported>,
+) -> ShortcutResolver {
+    resolver(Fixture {
+        app_id,
+        files: vec![fixture(file)],
+        import,
+    })
+}
+
+fn entries(r: &ShortcutResolver, app_id: &str) -> Vec<LoadedEntry> {
+    r.load(app_id).unwrap().unwrap().entries
+}
+
+fn find<'a>(entries: &'a [LoadedEntry], keys: &str) -> Vec<&'a LoadedEntry> {
+    entries.iter().filter(|e| e.keys == keys).collect()
+}
+
+/// The entry bound to `keys`, which must be the only one.
+fn only<'a>(entries: &'a [LoadedEntry], keys: &str) -> &'a LoadedEntry {
+    match find(entries, keys).as_slice() {
+        [e] => e,
+        other => panic!("expected one entry for {keys}, got {other:?}"),
+    }
+}
+
+fn warnings(r: &ShortcutResolver) -> Vec<&Diagnostic> {
+    r.all_diagnostics()
+        .into_iter()
+        .filter(|d| d.severity == Severity::Warning && !d.path.ends_with("code.json"))
+        .collect()
+}
+
+#[test]
+fn cosmic_config_replaces_the_desktop_pack() {
+    let r = resolver(Fixture {
+        app_id: "root",
+        files: vec![fixture("cosmic/defaults"), fixture("cosmic/custom")],
+        import: |f| cosmic::import_files(Some(&f[0]), Some(&f[1])),
+    });
+
+    let files = r.resolve("root").unwrap();
+    assert!(files
+        .iter()
+        .all(|f| !f.path.ends_with("cosmic-desktop.json")));
+
+    let e = entries(&r, "root");
+    assert_eq!(only(&e, "Super+Q").desc, "Close");
+    assert_eq!(only(&e, "Super+Q").layer, Layer::System);
+    assert_eq!(only(&e, "Super+Arrows").desc, "Focus neighbor");
+    assert_eq!(only(&e, "Super+1-9").desc, "Workspace 1-9");
+
+    // changed in custom
+    assert_eq!(only(&e, "Super+T").desc, "Run alacritty");
+    assert_eq!(only(&e, "Super+T").layer, Layer::User);
+    assert_eq!(only(&e, "Super+Ctrl+Enter").desc, "Scratch \"notes\"");
+    // disabled in custom
+    assert!(find(&e, "Super+Y").is_empty());
+
+    let w = warnings(&r);
+    assert_eq!(w.len(), 1, "{w:?}");
+    assert!(w[0].message.contains("Super+Hangul"));
+}
+
+#[test]
+fn cosmic_config_keeps_tiling_and_floating_apart() {
+    let r = resolver(Fixture {
+        app_id: "root",
+        files: vec![fixture("cosmic/defaults")],
+        import: |f| cosmic::import_files(Some(&f[0]), None),
+    });
+    let e = entries(&r, "root");
+    let view = |layout| -> Vec<(String, String)> {
+        e.iter()
+            .filter(|e| e.applies_to(Some(layout)))
+            .map(|e| (e.keys.clone(), e.desc.clone()))
+            .collect()
+    };
+    let (tiling, floating) = (view(Layout::Tiling), view(Layout::Floating));
+    let has = |view: &[(String, String)], keys: &str, desc: &str| {
+        view.contains(&(keys.to_string(), desc.to_string()))
+    };
+
+    assert_ne!(tiling, floating);
+    assert!(has(&tiling, "Super+Y", "Disable tiling"));
+    assert!(!has(&tiling, "Super+Y", "Enable tiling"));
+    assert!(has(&floating, "Super+Y", "Enable tiling"));
+    assert!(!has(&floating, "Super+Y", "Disable tiling"));
+    assert!(has(&tiling, "Super+G", "Floating toggle"));
+    assert!(!has(&floating, "Super+G", "Floating toggle"));
+    // the rest is on both
+    assert!(has(&floating, "Super+Q", "Close"));
+
+    // and everything while the layout is unknown
+    assert_eq!(find(&e, "Super+Y").len(), 2);
+}
+
+#[test]
+fn vscode_keybindings_apply_in_order_over_the_code_pack() {
+    let r = one_file("code", "vscode/keybindings.json", |f| {
+        vec![vscode::import_file(&f[0])]
+    });
+    let e = entries(&r, "code");
+
+    // added to a bundled command: takes its description
+    let d = only(&e, "Ctrl+D");
+    assert_eq!(d.desc, "Delete line");
+    assert_eq!(d.category.as_deref(), Some("Basic editing"));
+    assert_eq!(d.layer, Layer::User);
+    // removed by key, and every binding of a command
+    assert!(find(&e, "Ctrl+Shift+K").is_empty());
+    assert!(find(&e, "F11").is_empty());
+    // added, then removed again
+    assert!(find(&e, "Ctrl+K Ctrl+X").is_empty());
+    // not bundled: named after the command
+    assert_eq!(only(&e, "Ctrl+E").desc, "Reload window");
+    // same as bundled, despite the comment markers in a string
+    assert_eq!(only(&e, "Ctrl+,").layer, Layer::System);
+
+    let w = warnings(&r);
+    assert_eq!(w.len(), 1, "{w:?}");
+    assert!(w[0].message.contains("ctrl+numpad_add"));
+    assert_eq!(w[0].line, Some(15));
+}
+
+#[test]
+fn importer_app_ids_are_normalized_like_pack_ids() {
+    // an alias of code.json, spelt the way a compositor might report it
+    let r = one_file(" Com.VisualStudio.Code ", "vscode/keybindings.json", |f| {
+        vec![vscode::import_file(&f[0])]
+    });
+
+    // merged over the bundled pack rather than a pack of its own
+    assert_eq!(r.resolve("com.visualstudio.code").unwrap().len(), 2);
+    assert_eq!(r.resolve("code").unwrap().len(), 2);
+    assert!(r.packs().iter().all(|(id, _)| !id.contains("visualstudio")));
+    assert_eq!(only(&entries(&r, "code"), "Ctrl+D").desc, "Delete line");
+}
+
+#[test]
+fn obsidian_hotkeys_replace_the_bindings_of_a_command() {
+    let r = one_file("obsidian", "obsidian/hotkeys.json", |f| {
+        vec![obsidian::import_file(&f[0])]
+    });
+    let e = entries(&r, "obsidian");
+
+    assert!(find(&e, "Ctrl+D").is_empty());
+    assert_eq!(only(&e, "Ctrl+Shift+K").desc, "Delete current line");
+    assert_eq!(only(&e, "Ctrl+Shift+K").category.as_deref(), Some("Editor"));
+
+    assert_eq!(only(&e, "Alt+Left").desc, "Navigate back");
+    assert_eq!(only(&e, "Ctrl+[").desc, "Navigate back");
+    assert!(find(&e, "Ctrl+G").is_empty());
+    assert_eq!(only(&e, "Ctrl+B").desc, "Toggle bold");
+
+    let w = warnings(&r);
+    assert_eq!(w.len(), 1, "{w:?}");
+    assert!(w[0].message.contains("app:reload"));
+}
+
+#[test]
+fn gimp_shortcutsrc_sets_the_accelerators_of_an_action() {
+    let r = one_file("gimp", "gimp/shortcutsrc", |f| {
+        vec![gimp::import_file(&f[0])]
+    });
+    let e = entries(&r, "gimp");
+
+    assert_eq!(only(&e, "P").desc, "Paintbrush");
+    assert!(find(&e, "B").is_empty());
+    assert_eq!(only(&e, "Shift+C").desc, "Crop Tool");
+    assert!(find(&e, "C").is_empty());
+    assert!(find(&e, "Tab").is_empty());
+
+    assert_eq!(only(&e, "Ctrl+O").desc, "Open Image");
+    assert_eq!(only(&e, "F3").desc, "Open Image");
+    assert_eq!(only(&e, "Ctrl+Shift+F").desc, "Image flatten");
+    assert_eq!(only(&e, "Ctrl+Plus").desc, "View zoom in");
+
+    let w = warnings(&r);
+    assert_eq!(w.len(), 1, "{w:?}");
+    assert!(w[0].message.contains("XF86ZoomIn"));
+}
+
+#[test]
+fn gimp_menurc_skips_commented_defaults() {
+    let r = one_file("gimp", "gimp/menurc", |f| vec![gimp::import_file(&f[0])]);
+    let e = entries(&r, "gimp");
+
+    assert_eq!(only(&e, "P").desc, "Paintbrush");
+    assert!(find(&e, "Tab").is_empty());
+    assert_eq!(only(&e, "Ctrl+Shift+N").layer, Layer::User);
+    assert_eq!(only(&e, "Ctrl+O").layer, Layer::System);
+    assert!(warnings(&r).is_empty());
+}
+
+#[test]
+fn tmux_conf_rebinds_under_the_final_prefix() {
+    let r = one_file("tmux", "tmux/tmux.conf", |f| vec![tmux::import_file(&f[0])]);
+    let e = entries(&r, "tmux");
+
+    // every prefixed binding moves to the new prefix
+    assert!(find(&e, "Ctrl+B D").is_empty());
+    assert_eq!(only(&e, "Ctrl+A D").desc, "Detach");
+    assert_eq!(only(&e, "Ctrl+A Ctrl+A").desc, "Send prefix");
+    // unbound
+    assert!(find(&e, "Ctrl+A \"").is_empty());
+    assert!(find(&e, "Ctrl+A %").is_empty());
+
+    assert_eq!(
+        only(&e, "Ctrl+A |").desc,
+        "split-window -h -c #{pane_current_path}"
+    );
+    assert_eq!(only(&e, "Ctrl+A R").desc, "Reload the config");
+    // same command as bundled: keeps its name
+    assert_eq!(only(&e, "Ctrl+A C").desc, "New window");
+    assert_eq!(only(&e, "Ctrl+A C").category.as_deref(), Some("Windows"));
+    assert_eq!(only(&e, "Ctrl+A Shift+C").desc, "New session");
+    // root table, no prefix
+    assert_eq!(only(&e, "Alt+Left").desc, "select-pane -L");
+    assert!(find(&e, "V").is_empty());
+
+    let w = warnings(&r);
+    assert_eq!(w.len(), 1, "{w:?}");
+    assert!(w[0].message.contains("MouseDown3Pane"));
+    assert_eq!(w[0].line, Some(21));
+}
+
+#[test]
+fn terminal_contexts_nest_packs_by_title() {
+    let r = one_file("tmux", "tmux/tmux.conf", |f| vec![tmux::import_file(&f[0])]);
+    let mut pack = r.load("cosmic-term").unwrap().unwrap();
+
+    let shell = pack.entries_for_title("me@box: ~/src");
+    assert_eq!(only(&shell, "Ctrl+R").desc, "Search history");
+    assert_eq!(
+        only(&shell, "Ctrl+R").category.as_deref(),
+        Some("Shell: History")
+    );
+    assert_eq!(only(&shell, "Ctrl+Shift+C").desc, "Copy");
+    assert!(find(&shell, "Ctrl+A D").is_empty());
+
+    // tmux's default titles, with the imported prefix
+    let tmux = pack.entries_for_title("0:1:bash - \"me@box: ~/src\"");
+    let detach = only(&tmux, "Ctrl+A D");
+    assert_eq!(detach.category.as_deref(), Some("tmux: Sessions"));
+    assert_eq!(only(&tmux, "Ctrl+R").desc, "Search history");
+
+    // nothing in the title says vim, but a hint does
+    let title = "~/src orbitkeys:vim";
+    assert!(find(&pack.entries_for_title(title), "G G").is_empty());
+    pack.set_hinted(r.hinted_contexts(title));
+    let vim = pack.entries_for_title(title);
+    assert_eq!(only(&vim, "G G").category.as_deref(), Some("Vim: Motion"));
+    assert_eq!(pack.active_contexts(title)[0].name, "Vim");
+
+    pack.set_hinted(r.hinted_contexts("~/src orbitkeys:nothing"));
+    assert!(pack.contexts.iter().all(|c| !c.hinted));
+}
+
+#[test]
+fn unreadable_config_is_reported() {
+    let missing = fixture("vscode/missing.json");
+    let r = resolver(Fixture {
+        app_id: "code",
+        files: vec![missing.clone()],
+        import: |f| vec![vscode::import_file(&f[0])],
+    });
+
+    let errors: Vec<_> = r
+        .all_diagnostics()
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, missing);
+    // the bundled pack is still there
+    assert_eq!(
+        only(&entries(&r, "code"), "Ctrl+Shift+K").desc,
+        "Delete line"
+    );
+}
+
+#[test]
+fn importer_files_are_watched() {
+    let r = one_file("gimp", "gimp/shortcutsrc", |f| {
+        vec![gimp::import_file(&f[0])]
+    });
+
+    let set = r.watch_set();
+    assert_eq!(set.files, vec![fixture("gimp/shortcutsrc")]);
+    assert!(set.wants(&fixture("gimp/shortcutsrc")));
+    assert!(!set.wants(&fixture("gimp/menurc")));
+}
+
+#[test]
+fn every_importer_has_a_bundled_pack() {
+    let r = resolver(Fixture {
+        app_id: "root",
+        files: Vec::new(),
+        import: |_| Vec::new(),
+    });
+
+    for importer in super::all() {
+        assert!(r.resolve(importer.app_id()).is_some(), "{importer:?}");
+    }
+}
@@ -0,0 +1,343 @@
+//! tmux key bindings from `~/.tmux.conf`. Only the prefix and the bindings
+//! of the prefix and root tables are read; other commands, `source-file`
+//! included, are skipped:
+//!
+//! ```text
+//! set -g prefix C-a
+//! unbind '"'
+//! bind | split-window -h
+//! bind -n M-Left select-pane -L
+//! ```
+//!
+//! Prefix-table keys are relative to the prefix, so they are written out
+//! with the one in effect at the end of the file, like tmux does.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{config_home, home, Imported, ImportedContent, Importer};
+use crate::key_format::KeySequence;
+use crate::shortcut_overlay::{Overlay, OverlayEntry, Prefix, Selector};
+use crate::shortcut_pack::{Diagnostic, Severity};
+use crate::shortcut_resolver::Layer;
+
+/// tmux's prefix unless the config changes it; the bundled pack uses it.
+const DEFAULT_PREFIX: &str = "Ctrl+B";
+
+/// tmux's bindings, for the `tmux` pack.
+#[derive(Debug)]
+pub struct Tmux;
+
+impl Importer for Tmux {
+    fn app_id(&self) -> &str {
+        "tmux"
+    }
+
+    /// `~/.tmux.conf`, then `$XDG_CONFIG_HOME/tmux/tmux.conf`. The watcher
+    /// observes $HOME for the first, but reports nothing else from it.
+    fn files(&self) -> Vec<PathBuf> {
+        let dot = home().map(|h| h.join(".tmux.conf"));
+        let xdg = config_home().map(|d| d.join("tmux").join("tmux.conf"));
+        dot.into_iter().chain(xdg).collect()
+    }
+
+    /// The first file that exists.
+    fn import(&self) -> Vec<Imported> {
+        self.files()
+            .into_iter()
+            .find(|p| p.is_file())
+            .map(|p| vec![import_file(&p)])
+            .unwrap_or_default()
+    }
+}
+
+/// A binding, or with no `command` an unbinding, in the config's order.
+struct Binding {
+    prefixed: bool,
+    key: String,
+    command: Option<String>,
+    note: Option<String>,
+}
+
+/// Turn a `tmux.conf` into an overlay for the `tmux` pack.
+pub fn import_file(path: &Path) -> Imported {
+    let diag = |line: usize, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line: Some(line),
+        severity: Severity::Warning,
+        message,
+    };
+
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            return Imported {
+                layer: Layer::User,
+                path: path.to_path_buf(),
+                content: None,
+                diagnostics: vec![Diagnostic {
+                    path: path.to_path_buf(),
+                    line: None,
+                    severity: Severity::Error,
+                    message: e.to_string(),
+                }],
+            }
+        }
+    };
+
+    let mut prefix = DEFAULT_PREFIX.to_string();
+    let mut bindings: Vec<Binding> = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for (line, words) in commands(&raw) {
+        let Some((command, args)) = words.split_first() else {
+            continue;
+        };
+        let flags = Flags::parse(args);
+
+        match command.as_str() {
+            "set" | "set-option" => {
+                if let [name, value, ..] = flags.rest {
+                    if name == "prefix" {
+                        match tmux_key(value) {
+                            Some(k) => prefix = k,
+                            None => diagnostics
+                                .push(diag(line, format!("prefix \"{value}\" can't be shown"))),
+                        }
+                    }
+                }
+            }
+            "bind" | "bind-key" | "unbind" | "unbind-key" => {
+                let Some(prefixed) = flags.prefixed() else {
+                    continue;
+                };
+                // `unbind -a` empties a table; the bundled entries stay
+                let (Some(raw_key), false) = (flags.rest.first(), flags.all) else {
+                    continue;
+                };
+                let Some(key) = tmux_key(raw_key) else {
+                    diagnostics.push(diag(line, format!("key \"{raw_key}\" can't be shown")));
+                    continue;
+                };
+
+                let rest = &flags.rest[1..];
+                let command = command
+                    .starts_with('b')
+                    .then(|| command_text(rest))
+                    .filter(|c| !c.is_empty());
+                bindings.retain(|b| b.prefixed != prefixed || b.key != key);
+                bindings.push(Binding {
+                    prefixed,
+                    key,
+                    command,
+                    note: flags.note.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut overlay = Overlay::default();
+    if prefix != DEFAULT_PREFIX {
+        overlay.prefix.push(Prefix {
+            from: DEFAULT_PREFIX.to_string(),
+            to: prefix.clone(),
+        });
+    }
+    for b in bindings {
+        let keys = match b.prefixed {
+            true => format!("{prefix} {}", b.key),
+            false => b.key,
+        };
+        overlay.hide.push(Selector {
+            keys: Some(keys.clone()),
+            desc: None,
+            command: None,
+        });
+        if let Some(command) = b.command {
+            overlay.add.push(OverlayEntry {
+                keys,
+                desc: b.note.unwrap_or_default(),
+                category: None,
+                command: Some(command),
+            });
+        }
+    }
+
+    Imported {
+        layer: Layer::User,
+        path: path.to_path_buf(),
+        content: Some(ImportedContent::Overlay(overlay)),
+        diagnostics,
+    }
+}
+
+/// The flags of `bind`, `unbind` and `set` that matter here.
+struct Flags<'a> {
+    table: Option<&'a str>,
+    root: bool,
+    all: bool,
+    note: Option<String>,
+    /// Arguments after the flags.
+    rest: &'a [String],
+}
+
+impl<'a> Flags<'a> {
+    fn parse(args: &'a [String]) -> Self {
+        let mut flags = Flags {
+            table: None,
+            root: false,
+            all: false,
+            note: None,
+            rest: args,
+        };
+
+        // a lone "-" is a key, not a flag
+        while let Some((first, tail)) = flags.rest.split_first() {
+            let Some(letters) = first.strip_prefix('-').filter(|l| !l.is_empty()) else {
+                break;
+            };
+            flags.rest = tail;
+            for c in letters.chars() {
+                match c {
+                    'n' => flags.root = true,
+                    'a' => flags.all = true,
+                    // flags taking a value
+                    'T' | 'N' | 't' => {
+                        let Some((value, tail)) = flags.rest.split_first() else {
+                            break;
+                        };
+                        flags.rest = tail;
+                        match c {
+                            'T' => flags.table = Some(value.as_str()),
+                            'N' => flags.note = Some(value.clone()),
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        flags
+    }
+
+    /// Whether keys follow the prefix; `None` for tables other than the
+    /// prefix and root ones.
+    fn prefixed(&self) -> Option<bool> {
+        match (self.root, self.table) {
+            (true, _) | (_, Some("root")) => Some(false),
+            (_, None | Some("prefix")) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// The command a binding runs, as written: "split-window -h".
+fn command_text(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|w| match w.contains(char::is_whitespace) || w.is_empty() {
+            true => format!("\"{w}\""),
+            false => w.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The config's commands as words with quotes removed, each with the line
+/// it starts on. Lines ending in `\` continue on the next; an unquoted `#`
+/// starting a word comments out the rest of the line, and `;` separates
+/// commands.
+fn commands(raw: &str) -> Vec<(usize, Vec<String>)> {
+    let mut out = Vec::new();
+    let mut lines = raw.lines().enumerate();
+
+    while let Some((n, line)) = lines.next() {
+        let mut text = line.to_string();
+        while text.ends_with('\\') {
+            text.pop();
+            match lines.next() {
+                Some((_, next)) => text.push_str(next),
+                None => break,
+            }
+        }
+
+        let mut words: Vec<String> = Vec::new();
+        let mut word: Option<String> = None;
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                // but "#{...}" is a format
+                '#' if word.is_none() && chars.clone().next() != Some('{') => break,
+                c if c.is_whitespace() => words.extend(word.take()),
+                // `\;` is a literal ";" passed on to the bound command
+                '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+                ';' if word.is_none() => {
+                    out.push((n + 1, std::mem::take(&mut words)));
+                }
+                '\'' | '"' => {
+                    let w = word.get_or_insert_with(String::new);
+                    while let Some(q) = chars.next() {
+                        match q {
+                            _ if q == c => break,
+                            '\\' if c == '"' => w.extend(chars.next()),
+                            q => w.push(q),
+                        }
+                    }
+                }
+                c => word.get_or_insert_with(String::new).push(c),
+            }
+        }
+        words.extend(word);
+        out.push((n + 1, words));
+    }
+    out
+}
+
+/// tmux key -> OrbitKeys chord: "C-a" -> "Ctrl+A", "M-Left" -> "Alt+Left",
+/// "T" -> "Shift+T". `None` for keys without a label, like mouse events.
+fn tmux_key(key: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut rest = key;
+    loop {
+        let (modifier, tail) = match rest.get(..2) {
+            Some("C-") if rest.len() > 2 => ("Ctrl", &rest[2..]),
+            Some("M-") if rest.len() > 2 => ("Alt", &rest[2..]),
+            Some("S-") if rest.len() > 2 => ("Shift", &rest[2..]),
+            _ if rest.len() > 1 && rest.starts_with('^') => ("Ctrl", &rest[1..]),
+            _ => break,
+        };
+        parts.push(modifier);
+        rest = tail;
+    }
+
+    let mut chars = rest.chars();
+    let name = match (chars.next(), chars.next()) {
+        // tmux tells letters apart by case; under Ctrl it doesn't
+        (Some(c), None) if c.is_ascii_uppercase() && !parts.contains(&"Ctrl") => {
+            parts.push("Shift");
+            c.to_string()
+        }
+        (Some('+'), None) => "Plus".to_string(),
+        (Some(_), None) => rest.to_string(),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "bspace" => "Backspace".to_string(),
+            "btab" => {
+                parts.push("Shift");
+                "Tab".to_string()
+            }
+            "dc" => "Delete".to_string(),
+            "ic" => "Insert".to_string(),
+            "ppage" | "pageup" | "pgup" => "PageUp".to_string(),
+            "npage" | "pagedown" | "pgdn" => "PageDown".to_string(),
+            "escape" => "Esc".to_string(),
+            _ => rest.to_string(),
+        },
+    };
+
+    parts.push(&name);
+    KeySequence::parse(&parts.join("+"))
+        .ok()
+        .filter(|seq| seq.0.len() == 1)
+        .map(|seq| seq.to_string())
+}
@@ -0,0 +1,215 @@
+//! VS Code's own keybindings file, applied over the bundled `code` pack the
+//! way VS Code applies it over its defaults.
+//!
+//! `~/.config/Code/User/keybindings.json` is JSON with comments and trailing
+//! commas: a list of rules, where a `-` before the command removes a binding.
+//!
+//! ```jsonc
+//! [
+//!     // Quick Open on Ctrl+E as well
+//!     { "key": "ctrl+e", "command": "workbench.action.quickOpen" },
+//!     // and not on Ctrl+P
+//!     { "key": "ctrl+p", "command": "-workbench.action.quickOpen" },
+//! ]
+//! ```
+//!
+//! Entries of the bundled pack carry VS Code's command ids, so removals and
+//! added bindings line up with them. `when` clauses are ignored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{config_home, flatpak_config, Imported, ImportedContent, Importer};
+use crate::key_format::KeySequence;
+use crate::shortcut_overlay::{Overlay, OverlayEntry, Selector};
+use crate::shortcut_pack::{line_of, Diagnostic, Severity};
+use crate::shortcut_resolver::Layer;
+
+const FILE: &str = "keybindings.json";
+
+#[derive(Debug, Deserialize)]
+struct Rule {
+    /// Optional for removals: `{ "command": "-x" }` drops every binding of `x`.
+    #[serde(default)]
+    key: Option<String>,
+    command: String,
+}
+
+/// VS Code's user keybindings, for the `code` pack.
+#[derive(Debug)]
+pub struct VsCode;
+
+impl Importer for VsCode {
+    fn app_id(&self) -> &str {
+        "code"
+    }
+
+    /// `Code/User/keybindings.json` of the native and the Flatpak build.
+    fn files(&self) -> Vec<PathBuf> {
+        [config_home(), flatpak_config("com.visualstudio.code")]
+            .into_iter()
+            .flatten()
+            .map(|d| d.join("Code").join("User").join(FILE))
+            .collect()
+    }
+
+    /// The first keybindings file found.
+    fn import(&self) -> Vec<Imported> {
+        self.files()
+            .into_iter()
+            .find(|p| p.is_file())
+            .map(|p| vec![import_file(&p)])
+            .unwrap_or_default()
+    }
+}
+
+/// Turn one keybindings file into an overlay for the `code` pack.
+pub fn import_file(path: &Path) -> Imported {
+    let diag = |severity, line: Option<usize>, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line,
+        severity,
+        message,
+    };
+    let failed = |d: Diagnostic| Imported {
+        layer: Layer::User,
+        path: path.to_path_buf(),
+        content: None,
+        diagnostics: vec![d],
+    };
+
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => strip_jsonc(&raw),
+        Err(e) => return failed(diag(Severity::Error, None, e.to_string())),
+    };
+    let rules: Vec<Rule> = match serde_json::from_str(&raw) {
+        Ok(rules) => rules,
+        Err(e) => return failed(diag(Severity::Error, Some(e.line()), e.to_string())),
+    };
+
+    let mut overlay = Overlay::default();
+    let mut diagnostics = Vec::new();
+
+    for rule in rules {
+        let keys = match rule.key.as_deref().map(KeySequence::parse) {
+            None => None,
+            Some(Ok(seq)) => Some(seq.to_string()),
+            Some(Err(e)) => {
+                let key = rule.key.unwrap_or_default();
+                diagnostics.push(diag(
+                    Severity::Warning,
+                    line_of(&raw, &key),
+                    format!("key \"{key}\" can't be shown: {e}"),
+                ));
+                continue;
+            }
+        };
+
+        if let Some(command) = rule.command.strip_prefix('-') {
+            // Rules apply in order, so this also undoes earlier ones here.
+            let removed = |a: &OverlayEntry| {
+                a.command.as_deref() == Some(command) && keys.as_ref().is_none_or(|k| *k == a.keys)
+            };
+            overlay.add.retain(|a| !removed(a));
+            overlay.hide.push(Selector {
+                keys,
+                desc: None,
+                command: Some(command.to_string()),
+            });
+        } else if let Some(keys) = keys {
+            let dup = overlay
+                .add
+                .iter()
+                .any(|a| a.keys == keys && a.command.as_deref() == Some(&rule.command));
+            if !dup {
+                overlay.add.push(OverlayEntry {
+                    keys,
+                    desc: String::new(),
+                    category: None,
+                    command: Some(rule.command),
+                });
+            }
+        }
+    }
+
+    Imported {
+        layer: Layer::User,
+        path: path.to_path_buf(),
+        content: Some(ImportedContent::Overlay(overlay)),
+        diagnostics,
+    }
+}
+
+/// Blank out comments and trailing commas so serde_json can read the file.
+/// Everything keeps its line, so parse errors point at the right place.
+fn strip_jsonc(raw: &str) -> String {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut out = Vec::with_capacity(chars.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' if i + 1 < chars.len() => {
+                    out.push(chars[i + 1]);
+                    i += 1;
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match (c, chars.get(i + 1)) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    out.push(' ');
+                    i += 1;
+                }
+                continue;
+            }
+            ('/', Some('*')) => {
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or(chars.len(), |j| j + 2);
+                out.extend(
+                    chars[i..end]
+                        .iter()
+                        .map(|&c| if c == '\n' { '\n' } else { ' ' }),
+                );
+                i = end;
+                continue;
+            }
+            ('"', _) => in_string = true,
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+
+    // Trailing commas: a comma whose next non-space character closes the list.
+    let mut in_string = false;
+    let mut escaped = false;
+    for i in 0..out.len() {
+        match out[i] {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                let next = out[i + 1..].iter().find(|c| !c.is_whitespace());
+                if matches!(next, Some(']' | '}')) {
+                    out[i] = ' ';
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out.into_iter().collect()
+}
@@ -1,46 +1,367 @@
+use std::fmt;
+
 use crate::key_glyphs::KeyGlyph;
+use crate::key_layout::KeyLayout;
+
+#[cfg(test)]
+mod tests;
+
+/// Modifier set of a single chord. Rendered in legend order (Super, Ctrl, Alt, AltGr, Shift)
+/// regardless of how the JSON spelled it, so "Shift+Ctrl+G" and "Ctrl+Shift+G" agree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub super_key: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub altgr: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    pub fn is_empty(&self) -> bool {
+        !(self.super_key || self.ctrl || self.alt || self.altgr || self.shift)
+    }
+
+    pub fn glyphs(&self) -> impl Iterator<Item = KeyGlyph> {
+        [
+            (self.super_key, KeyGlyph::Super),
+            (self.ctrl, KeyGlyph::Ctrl),
+            (self.alt, KeyGlyph::Alt),
+            (self.altgr, KeyGlyph::AltGr),
+            (self.shift, KeyGlyph::Shift),
+        ]
+        .into_iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, g)| g)
+    }
+
+    /// Returns false if the modifier was already set.
+    fn insert(&mut self, g: KeyGlyph) -> bool {
+        let slot = match g {
+            KeyGlyph::Super => &mut self.super_key,
+            KeyGlyph::Ctrl => &mut self.ctrl,
+            KeyGlyph::Alt => &mut self.alt,
+            KeyGlyph::AltGr => &mut self.altgr,
+            KeyGlyph::Shift => &mut self.shift,
+            _ => return false,
+        };
+        !std::mem::replace(slot, true)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Click,
+    DoubleClick,
+    RightClick,
+    MiddleClick,
+    Scroll,
+    Drag,
+}
+
+impl MouseButton {
+    fn from_name(name: &str) -> Option<Self> {
+        let b = match name.to_ascii_lowercase().as_str() {
+            "click" | "leftclick" => MouseButton::Click,
+            "doubleclick" => MouseButton::DoubleClick,
+            "rightclick" => MouseButton::RightClick,
+            "middleclick" => MouseButton::MiddleClick,
+            "scroll" | "wheel" => MouseButton::Scroll,
+            "drag" => MouseButton::Drag,
+            _ => return None,
+        };
+        Some(b)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MouseButton::Click => "Click",
+            MouseButton::DoubleClick => "DoubleClick",
+            MouseButton::RightClick => "RightClick",
+            MouseButton::MiddleClick => "MiddleClick",
+            MouseButton::Scroll => "Scroll",
+            MouseButton::Drag => "Drag",
+        }
+    }
+}
+
+/// Key groups a pack may write as one key; any other run of capitals is a typo
+/// ("PGUPP", "ESCC") rather than a group.
+const KEY_SETS: &[&str] = &["HJKL", "WASD"];
+
+/// The non-modifier part of a chord.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A key with a glyph (Tab, Arrows, ...). Also used for bare modifiers like "Super".
+    Named(KeyGlyph),
+    /// F1..F24
+    Function(u8),
+    /// A printable key: letters (stored uppercase), digits, punctuation.
+    Char(char),
+    /// "1-9", "A-Z": any key in the range.
+    Range(char, char),
+    /// "HJKL", "WASD": any of the listed keys (see [`KEY_SETS`]).
+    Set(Vec<char>),
+    Mouse(MouseButton),
+}
+
+impl Key {
+    fn parse(token: &str) -> Result<Self, KeyParseError> {
+        if token.is_empty() {
+            return Err(KeyParseError::EmptyToken);
+        }
+
+        if let Some(g) = KeyGlyph::from_name(token) {
+            return Ok(Key::Named(g));
+        }
+        if let Some(b) = MouseButton::from_name(token) {
+            return Ok(Key::Mouse(b));
+        }
+
+        let chars: Vec<char> = token.chars().collect();
+        match chars.as_slice() {
+            [c] => return Ok(Key::Char(c.to_ascii_uppercase())),
+            [a, '-', b] if a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() => {
+                let (a, b) = (a.to_ascii_uppercase(), b.to_ascii_uppercase());
+                if a >= b || a.is_ascii_digit() != b.is_ascii_digit() {
+                    return Err(KeyParseError::BadRange(token.to_string()));
+                }
+                return Ok(Key::Range(a, b));
+            }
+            _ => {}
+        }
+
+        if let Some(n) = token
+            .strip_prefix(['F', 'f'])
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| (1..=24).contains(n))
+        {
+            return Ok(Key::Function(n));
+        }
+
+        if KEY_SETS.contains(&token) {
+            return Ok(Key::Set(chars));
+        }
+
+        Err(KeyParseError::UnknownKey(token.to_string()))
+    }
+
+    /// The key for a keycap character, spelled as a pack would.
+    fn from_keycap(c: char) -> Self {
+        match c {
+            '+' => Key::Named(KeyGlyph::Plus),
+            '-' => Key::Named(KeyGlyph::Minus),
+            c => Key::Char(c.to_ascii_uppercase()),
+        }
+    }
+
+    pub fn glyph(&self) -> String {
+        match self {
+            Key::Named(g) => g.as_str().to_string(),
+            other => other.label(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Key::Named(g) => g.label().to_string(),
+            Key::Function(n) => format!("F{n}"),
+            Key::Char(c) => c.to_string(),
+            Key::Range(a, b) => format!("{a}-{b}"),
+            Key::Set(cs) => cs.iter().collect(),
+            Key::Mouse(b) => b.label().to_string(),
+        }
+    }
+}
+
+/// One chord: modifiers held together with a single key, e.g. "Ctrl+Shift+P".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn parse(raw: &str) -> Result<Self, KeyParseError> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Err(KeyParseError::Empty);
+        }
+
+        // "Ctrl++" / "+" — a literal plus as the key.
+        let (head, key_token) = if raw == "+" {
+            ("", "+")
+        } else if let Some(head) = raw.strip_suffix("++") {
+            (head, "+")
+        } else {
+            match raw.rsplit_once('+') {
+                Some((head, key)) => (head, key),
+                None => ("", raw),
+            }
+        };
+
+        let mut modifiers = Modifiers::default();
+        if !head.is_empty() {
+            for token in head.split('+') {
+                let token = token.trim();
+                match KeyGlyph::from_name(token) {
+                    Some(g) if g.is_modifier() => {
+                        if !modifiers.insert(g) {
+                            return Err(KeyParseError::DuplicateModifier(token.to_string()));
+                        }
+                    }
+                    _ if token.is_empty() => return Err(KeyParseError::EmptyToken),
+                    _ => return Err(KeyParseError::NotAModifier(token.to_string())),
+                }
+            }
+        }
+
+        let key = Key::parse(key_token.trim())?;
+        Ok(Self { modifiers, key })
+    }
+
+    /// The chord as pressed on `layout`: a character key becomes the keycap
+    /// typing it, plus Shift or AltGr if needed ("Ctrl+/" -> "Ctrl+Shift+7" on
+    /// German). Keys the layout lacks, and named keys, stay as they are;
+    /// "Plus" too, as apps take it with or without Shift.
+    pub fn physical(&self, layout: &KeyLayout) -> KeyChord {
+        let c = match &self.key {
+            Key::Char(c) => *c,
+            Key::Named(KeyGlyph::Minus) => '-',
+            // "HJKL" only if every key is typed without extra modifiers
+            Key::Set(cs) => {
+                let caps: Option<Vec<char>> = cs
+                    .iter()
+                    .map(|c| layout.keycap(*c).filter(|k| !k.shift && !k.altgr))
+                    .map(|k| k.map(|k| k.key.to_ascii_uppercase()))
+                    .collect();
+                return match caps {
+                    Some(caps) => KeyChord {
+                        modifiers: self.modifiers,
+                        key: Key::Set(caps),
+                    },
+                    None => self.clone(),
+                };
+            }
+            _ => return self.clone(),
+        };
+
+        let Some(cap) = layout.keycap(c) else {
+            return self.clone();
+        };
+        let mut modifiers = self.modifiers;
+        modifiers.shift |= cap.shift;
+        modifiers.altgr |= cap.altgr;
+        KeyChord {
+            modifiers,
+            key: Key::from_keycap(cap.key),
+        }
+    }
+
+    /// Compact glyph form, e.g. "⌃⇧P".
+    pub fn glyphs(&self) -> String {
+        let mut out: String = self.modifiers.glyphs().map(KeyGlyph::as_str).collect();
+        out.push_str(&self.key.glyph());
+        out
+    }
+}
+
+impl fmt::Display for KeyChord {
+    /// Canonical text form, e.g. "Ctrl+Shift+P".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for g in self.modifiers.glyphs() {
+            write!(f, "{}+", g.label())?;
+        }
+        f.write_str(&self.key.label())
+    }
+}
+
+/// Chords pressed one after another, e.g. "Ctrl+K Ctrl+W".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    pub fn parse(raw: &str) -> Result<Self, KeyParseError> {
+        let chords = raw
+            .split_whitespace()
+            .map(KeyChord::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if chords.is_empty() {
+            return Err(KeyParseError::Empty);
+        }
+        Ok(Self(chords))
+    }
+
+    pub fn chords(&self) -> &[KeyChord] {
+        &self.0
+    }
+
+    /// Every chord as pressed on `layout`; see [`KeyChord::physical`].
+    pub fn physical(&self, layout: &KeyLayout) -> KeySequence {
+        Self(self.0.iter().map(|c| c.physical(layout)).collect())
+    }
+
+    /// Glyph rendering used in the shortcut grid.
+    pub fn glyphs(&self) -> String {
+        self.0
+            .iter()
+            .map(KeyChord::glyphs)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Lowercased labels the search box matches key terms against, modifiers
+    /// included: "Ctrl+Shift+P" -> ["ctrl", "shift", "p"]. A literal "+" is
+    /// "plus", as the search can't tell it from a separator.
+    pub fn search_tokens(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .flat_map(|c| {
+                let modifiers = c.modifiers.glyphs().map(|g| g.label().to_lowercase());
+                let key = match c.key {
+                    Key::Char('+') => KeyGlyph::Plus.label().to_lowercase(),
+                    ref key => key.label().to_lowercase(),
+                };
+                modifiers.chain([key])
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyParseError {
+    Empty,
+    EmptyToken,
+    UnknownKey(String),
+    NotAModifier(String),
+    DuplicateModifier(String),
+    BadRange(String),
+}
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyParseError::Empty => f.write_str("empty key string"),
+            KeyParseError::EmptyToken => f.write_str("empty key between '+' separators"),
+            KeyParseError::UnknownKey(k) => write!(f, "unknown key \"{k}\""),
+            KeyParseError::NotAModifier(k) => write!(f, "\"{k}\" is not a modifier"),
+            KeyParseError::DuplicateModifier(k) => write!(f, "modifier \"{k}\" repeated"),
+            KeyParseError::BadRange(k) => write!(f, "range \"{k}\" isn't low to high"),
+        }
+    }
+}
 
-pub fn pretty_keys(raw: &str) -> String {
-    let mut s = raw.to_string();
-
-    let map = [
-        // Modifiers with pluses
-        ("Ctrl+", KeyGlyph::Ctrl),
-        ("Shift+", KeyGlyph::Shift),
-        ("Alt+", KeyGlyph::Alt),
-        ("Super+", KeyGlyph::Super),
-
-        // Standalone keys
-        ("Tab", KeyGlyph::Tab),
-        ("Enter", KeyGlyph::Enter),
-        ("Esc", KeyGlyph::Escape),
-        ("Escape", KeyGlyph::Escape),
-        ("Backspace", KeyGlyph::Backspace),
-
-        // Directionals
-        ("Arrows", KeyGlyph::Arrows), // THE FIX: Plural first
-        ("Arrow", KeyGlyph::Arrows),  // Catch singular too
-        ("Left", KeyGlyph::Left),
-        ("Right", KeyGlyph::Right),
-        ("Up", KeyGlyph::Up),
-        ("Down", KeyGlyph::Down),
-
-        ("Plus", KeyGlyph::Plus),
-        ("Minus", KeyGlyph::Minus),
-        ("Space", KeyGlyph::Space),
-        
-        // Final fallback for modifiers without a trailing plus
-        ("Ctrl", KeyGlyph::Ctrl),
-        ("Shift", KeyGlyph::Shift),
-        ("Alt", KeyGlyph::Alt),
-        ("Super", KeyGlyph::Super),
-    ];
-
-    for (needle, glyph) in map {
-        // Use glyph.as_str() for the icon, and maybe add a space 
-        // if you want the icons to be separated.
-        s = s.replace(needle, glyph.as_str());
-    }
-
-    s
-}
\ No newline at end of file
+impl std::error::Error for KeyParseError {}
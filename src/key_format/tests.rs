@@ -0,0 +1,102 @@
+use super::{Key, KeyChord, KeyParseError, KeySequence, Modifiers, MouseButton};
+use crate::key_glyphs::KeyGlyph;
+
+fn chord(raw: &str) -> KeyChord {
+    KeyChord::parse(raw).unwrap()
+}
+
+fn sequence(raw: &str) -> KeySequence {
+    KeySequence::parse(raw).unwrap()
+}
+
+#[test]
+fn sequences_split_on_spaces() {
+    let seq = sequence("Ctrl+K  Ctrl+W");
+    assert_eq!(seq.chords(), [chord("Ctrl+K"), chord("Ctrl+W")]);
+    assert_eq!(seq.to_string(), "Ctrl+K Ctrl+W");
+    assert_eq!(seq.glyphs(), "⌃K ⌃W");
+    assert_eq!(seq.search_tokens(), ["ctrl", "k", "ctrl", "w"]);
+}
+
+#[test]
+fn ranges_are_one_key() {
+    let c = chord("Super+1-9");
+    assert_eq!(c.key, Key::Range('1', '9'));
+    assert_eq!(c.to_string(), "Super+1-9");
+
+    // lowercase letters are stored as keycaps
+    assert_eq!(chord("Alt+a-z").key, Key::Range('A', 'Z'));
+
+    // backwards, empty, or from digits into letters
+    for range in ["9-1", "z-a", "5-5", "1-Z"] {
+        assert_eq!(
+            KeyChord::parse(&format!("Super+{range}")),
+            Err(KeyParseError::BadRange(range.to_string()))
+        );
+    }
+}
+
+#[test]
+fn sets_list_their_keys() {
+    let c = chord("Alt+HJKL");
+    assert_eq!(c.key, Key::Set(vec!['H', 'J', 'K', 'L']));
+    assert_eq!(c.glyphs(), "⎇HJKL");
+    assert_eq!(chord("WASD").key, Key::Set(vec!['W', 'A', 'S', 'D']));
+    assert!(KeyChord::parse("Alt+hjkl").is_err());
+
+    // capitals that aren't a known group are typos, not sets
+    for typo in ["PGUPP", "ESCC", "HJK"] {
+        assert_eq!(
+            KeyChord::parse(typo),
+            Err(KeyParseError::UnknownKey(typo.to_string()))
+        );
+    }
+}
+
+#[test]
+fn mouse_tokens() {
+    assert_eq!(chord("Alt+Click").key, Key::Mouse(MouseButton::Click));
+    assert_eq!(chord("Ctrl+Wheel").key, Key::Mouse(MouseButton::Scroll));
+    assert_eq!(chord("Shift+doubleclick").to_string(), "Shift+DoubleClick");
+}
+
+#[test]
+fn backspace_is_not_space() {
+    assert_eq!(chord("Backspace").key, Key::Named(KeyGlyph::Backspace));
+    assert_eq!(chord("Space").key, Key::Named(KeyGlyph::Space));
+    assert_eq!(chord("Ctrl+Backspace").glyphs(), "⌃⌫");
+    assert_eq!(chord("Ctrl+Space").glyphs(), "⌃␣");
+}
+
+#[test]
+fn modifiers_render_in_legend_order() {
+    assert_eq!(chord("Shift+Ctrl+G"), chord("Ctrl+Shift+G"));
+    assert_eq!(chord("Shift+Ctrl+G").to_string(), "Ctrl+Shift+G");
+    assert_eq!(chord("Shift+Alt+Super+Up").glyphs(), "⌘⎇⇧↑");
+    assert_eq!(
+        chord("Shift+Ctrl+G").modifiers,
+        Modifiers {
+            ctrl: true,
+            shift: true,
+            ..Modifiers::default()
+        }
+    );
+}
+
+#[test]
+fn literal_plus() {
+    assert_eq!(chord("+").key, Key::Char('+'));
+    assert_eq!(chord("Ctrl++").to_string(), "Ctrl++");
+
+    // searched as the Plus key either way
+    assert_eq!(sequence("Ctrl++").search_tokens(), ["ctrl", "plus"]);
+    assert_eq!(sequence("Ctrl+Plus").search_tokens(), ["ctrl", "plus"]);
+}
+
+#[test]
+fn bad_keys_are_errors() {
+    assert!(KeySequence::parse("  ").is_err());
+    assert!(KeyChord::parse("Ctrl+Ctrl+A").is_err());
+    assert!(KeyChord::parse("A+B").is_err());
+    assert!(KeyChord::parse("Ctrl+Nope").is_err());
+}
@@ -1,13 +1,22 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyGlyph {
     Ctrl,
     Shift,
     Alt,
+    /// ISO level 3 shift, the right Alt on most non-US layouts.
+    AltGr,
     Super,
     Tab,
     Enter,
     Escape,
     Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Print,
     Left,
     Right,
     Up,
@@ -26,11 +35,19 @@ impl KeyGlyph {
             KeyGlyph::Ctrl => "⌃",
             KeyGlyph::Shift => "⇧",
             KeyGlyph::Alt => "⎇",
+            KeyGlyph::AltGr => "⇮",
             KeyGlyph::Super => "⌘",
             KeyGlyph::Tab => "⇥",
             KeyGlyph::Enter => "↵",
             KeyGlyph::Escape => "⎋",
             KeyGlyph::Backspace => "⌫",
+            KeyGlyph::Delete => "⌦",
+            KeyGlyph::Insert => "⎀",
+            KeyGlyph::Home => "⇱",
+            KeyGlyph::End => "⇲",
+            KeyGlyph::PageUp => "⇞",
+            KeyGlyph::PageDown => "⇟",
+            KeyGlyph::Print => "⎙",
             KeyGlyph::Left => "←",
             KeyGlyph::Right => "→",
             KeyGlyph::Up => "↑",
@@ -48,11 +65,19 @@ impl KeyGlyph {
             KeyGlyph::Ctrl => "Ctrl",
             KeyGlyph::Shift => "Shift",
             KeyGlyph::Alt => "Alt",
+            KeyGlyph::AltGr => "AltGr",
             KeyGlyph::Super => "Super",
             KeyGlyph::Tab => "Tab",
             KeyGlyph::Enter => "Enter",
             KeyGlyph::Escape => "Esc",
             KeyGlyph::Backspace => "Backspace",
+            KeyGlyph::Delete => "Delete",
+            KeyGlyph::Insert => "Insert",
+            KeyGlyph::Home => "Home",
+            KeyGlyph::End => "End",
+            KeyGlyph::PageUp => "PageUp",
+            KeyGlyph::PageDown => "PageDown",
+            KeyGlyph::Print => "Print",
             KeyGlyph::Left => "Left",
             KeyGlyph::Right => "Right",
             KeyGlyph::Up => "Up",
@@ -64,10 +89,83 @@ impl KeyGlyph {
         }
     }
 
+    /// Looks up a key by name as written in shortcut JSON (case-insensitive,
+    /// accepting the common aliases).
+    pub fn from_name(name: &str) -> Option<KeyGlyph> {
+        let g = match name.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyGlyph::Ctrl,
+            "shift" => KeyGlyph::Shift,
+            "alt" | "option" => KeyGlyph::Alt,
+            "altgr" => KeyGlyph::AltGr,
+            "super" | "meta" | "win" | "logo" | "cmd" => KeyGlyph::Super,
+            "tab" => KeyGlyph::Tab,
+            "enter" | "return" => KeyGlyph::Enter,
+            "esc" | "escape" => KeyGlyph::Escape,
+            "backspace" => KeyGlyph::Backspace,
+            "delete" | "del" => KeyGlyph::Delete,
+            "insert" | "ins" => KeyGlyph::Insert,
+            "home" => KeyGlyph::Home,
+            "end" => KeyGlyph::End,
+            "pageup" | "pgup" => KeyGlyph::PageUp,
+            "pagedown" | "pgdn" => KeyGlyph::PageDown,
+            "print" | "printscreen" => KeyGlyph::Print,
+            "left" => KeyGlyph::Left,
+            "right" => KeyGlyph::Right,
+            "up" => KeyGlyph::Up,
+            "down" => KeyGlyph::Down,
+            "arrows" | "arrow" => KeyGlyph::Arrows,
+            "plus" => KeyGlyph::Plus,
+            "minus" => KeyGlyph::Minus,
+            "space" => KeyGlyph::Space,
+            _ => return None,
+        };
+        Some(g)
+    }
+
+    /// Reverse of [`Self::as_str`] for one symbol; either arrow of "↕↔" is Arrows.
+    pub fn from_glyph(c: char) -> Option<Self> {
+        let g = match c {
+            '⌃' => KeyGlyph::Ctrl,
+            '⇧' => KeyGlyph::Shift,
+            '⎇' => KeyGlyph::Alt,
+            '⇮' => KeyGlyph::AltGr,
+            '⌘' => KeyGlyph::Super,
+            '⇥' => KeyGlyph::Tab,
+            '↵' => KeyGlyph::Enter,
+            '⎋' => KeyGlyph::Escape,
+            '⌫' => KeyGlyph::Backspace,
+            '⌦' => KeyGlyph::Delete,
+            '⎀' => KeyGlyph::Insert,
+            '⇱' => KeyGlyph::Home,
+            '⇲' => KeyGlyph::End,
+            '⇞' => KeyGlyph::PageUp,
+            '⇟' => KeyGlyph::PageDown,
+            '⎙' => KeyGlyph::Print,
+            '←' => KeyGlyph::Left,
+            '→' => KeyGlyph::Right,
+            '↑' => KeyGlyph::Up,
+            '↓' => KeyGlyph::Down,
+            '↕' | '↔' => KeyGlyph::Arrows,
+            '+' => KeyGlyph::Plus,
+            '−' => KeyGlyph::Minus,
+            '␣' => KeyGlyph::Space,
+            _ => return None,
+        };
+        Some(g)
+    }
+
+    pub fn is_modifier(self) -> bool {
+        matches!(
+            self,
+            KeyGlyph::Ctrl | KeyGlyph::Shift | KeyGlyph::Alt | KeyGlyph::AltGr | KeyGlyph::Super
+        )
+    }
+
     pub const LEGEND: &'static [KeyGlyph] = &[
         KeyGlyph::Super,
         KeyGlyph::Ctrl,
         KeyGlyph::Alt,
+        KeyGlyph::AltGr,
         KeyGlyph::Shift,
         KeyGlyph::Tab,
         KeyGlyph::Enter,
@@ -76,4 +174,4 @@ impl KeyGlyph {
         KeyGlyph::Space,
         KeyGlyph::Arrows, // Added to legend
     ];
-}
\ No newline at end of file
+}
@@ -0,0 +1,207 @@
+//! Where characters live on the user's keyboard layout, for showing the keys
+//! to press instead of the characters packs name. Packs are written for US
+//! QWERTY: "Ctrl+/" is typed as Ctrl+Shift+7 on a German layout.
+//!
+//! The layout comes from the compositor's `wl_keyboard` keymap (see
+//! `focus_watcher`), or before that arrives, from COSMIC's input config. A
+//! keymap may hold several layouts (xkb groups); the compositor says which
+//! one is active.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use xkbcommon::xkb;
+
+#[cfg(test)]
+mod tests;
+
+/// COSMIC's keyboard config, under the config or data dirs.
+const COSMIC_XKB_CONFIG: &str = "cosmic/com.system76.CosmicComp/v1/xkb_config";
+
+/// The key that types a character, and what has to be held with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keycap {
+    /// What the key types on its own, as printed on it.
+    pub key: char,
+    pub shift: bool,
+    pub altgr: bool,
+}
+
+/// The layouts (groups) of a keymap reduced to their keycaps, one of them
+/// active.
+#[derive(Debug, Clone, Default)]
+pub struct KeyLayout {
+    groups: Vec<Group>,
+    active: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    name: String,
+    keycaps: HashMap<char, Keycap>,
+}
+
+impl KeyLayout {
+    /// From a keymap in the xkb text format, as `wl_keyboard` sends it.
+    pub fn from_keymap(raw: &str) -> Option<Self> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let raw = raw.trim_end_matches('\0').to_string();
+        let keymap = xkb::Keymap::new_from_string(
+            &context,
+            raw,
+            xkb::KEYMAP_FORMAT_TEXT_V1,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )?;
+        Some(Self::from_xkb(&keymap))
+    }
+
+    /// From rule names, e.g. layout "de" with variant "nodeadkeys". Empty
+    /// names take the system defaults.
+    pub fn from_names(
+        model: &str,
+        layout: &str,
+        variant: &str,
+        options: Option<String>,
+    ) -> Option<Self> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_names(
+            &context,
+            "",
+            model,
+            layout,
+            variant,
+            options,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )?;
+        Some(Self::from_xkb(&keymap))
+    }
+
+    /// From COSMIC's `xkb_config`, the user's or else the system default.
+    /// Every layout it lists is a group ("de,us" with variants
+    /// "nodeadkeys,"); the first is active. `None` outside COSMIC.
+    pub fn from_cosmic_config() -> Option<Self> {
+        let raw = cosmic_config_paths()
+            .into_iter()
+            .find_map(|p| fs::read_to_string(p).ok())?;
+        let field = |name: &str| ron_string(&raw, name).unwrap_or_default();
+
+        Self::from_names(
+            &field("model"),
+            &field("layout"),
+            &field("variant"),
+            ron_string(&raw, "options"),
+        )
+    }
+
+    fn from_xkb(keymap: &xkb::Keymap) -> Self {
+        Self {
+            groups: (0..keymap.num_layouts())
+                .map(|group| Group::from_xkb(keymap, group))
+                .collect(),
+            active: 0,
+        }
+    }
+
+    /// Make `group` the active layout, as the compositor reports it. Groups
+    /// past the last wrap around, as in xkb.
+    pub fn set_group(&mut self, group: u32) {
+        if !self.groups.is_empty() {
+            self.active = group as usize % self.groups.len();
+        }
+    }
+
+    /// The active layout's name, e.g. "German".
+    pub fn name(&self) -> &str {
+        self.groups.get(self.active).map_or("", |g| &g.name)
+    }
+
+    /// The key typing `c` on the active layout. Letters are looked up in
+    /// lower case.
+    pub fn keycap(&self, c: char) -> Option<Keycap> {
+        let c = match c.is_alphabetic() {
+            true => c.to_lowercase().next()?,
+            false => c,
+        };
+        self.groups.get(self.active)?.keycaps.get(&c).copied()
+    }
+}
+
+impl Group {
+    fn from_xkb(keymap: &xkb::Keymap, group: xkb::LayoutIndex) -> Self {
+        let mask = |name: &str| match keymap.mod_get_index(name) {
+            i if i < 32 => 1 << i,
+            _ => 0,
+        };
+        let shift = mask(xkb::MOD_NAME_SHIFT);
+        let altgr = mask(xkb::MOD_NAME_ISO_LEVEL3_SHIFT);
+
+        let mut state = xkb::State::new(keymap);
+        let mut keycaps = HashMap::new();
+        // plainest first, so a character on two keys gets the one needing less
+        for mods in [0, shift, altgr, shift | altgr] {
+            state.update_mask(mods, 0, 0, 0, 0, group);
+            keymap.key_for_each(|keymap, key| {
+                let Some(base) = keymap.key_get_syms_by_level(key, group, 0).first() else {
+                    return;
+                };
+                // the keypad has its own "/" and "*"; they aren't what's meant
+                if base.is_keypad_key() {
+                    return;
+                }
+                let (Some(cap), Some(c)) = (
+                    printable(xkb::keysym_to_utf32(*base)),
+                    printable(state.key_get_utf32(key)),
+                ) else {
+                    return;
+                };
+                keycaps.entry(c).or_insert(Keycap {
+                    key: cap,
+                    shift: mods & shift != 0,
+                    altgr: mods & altgr != 0,
+                });
+            });
+        }
+
+        Self {
+            name: keymap.layout_get_name(group).to_string(),
+            keycaps,
+        }
+    }
+}
+
+fn printable(utf32: u32) -> Option<char> {
+    char::from_u32(utf32).filter(|c| !c.is_control() && !c.is_whitespace())
+}
+
+/// `$XDG_CONFIG_HOME/<config>`, then `$XDG_DATA_DIRS/<config>`.
+fn cosmic_config_paths() -> Vec<PathBuf> {
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|h| PathBuf::from(h).join(".config"))
+        });
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    config_home
+        .into_iter()
+        .chain(std::env::split_paths(&data_dirs))
+        .map(|d| d.join(COSMIC_XKB_CONFIG))
+        .collect()
+}
+
+/// A string field of a RON struct such as `(layout: "de", options: Some("..."))`.
+fn ron_string(raw: &str, field: &str) -> Option<String> {
+    let start = raw.find(&format!("{field}:"))? + field.len() + 1;
+    let rest = raw[start..].trim_start();
+    let rest = rest.strip_prefix("Some(").unwrap_or(rest).trim_start();
+    let value = rest.strip_prefix('"')?;
+    value.find('"').map(|end| value[..end].to_string())
+}
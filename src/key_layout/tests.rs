@@ -0,0 +1,55 @@
+use super::{KeyLayout, Keycap};
+use crate::key_format::KeySequence;
+
+const DE_US: &str = include_str!("fixtures/de_us.xkb");
+
+fn layout() -> KeyLayout {
+    KeyLayout::from_keymap(DE_US).expect("fixture keymap compiles")
+}
+
+fn physical(layout: &KeyLayout, keys: &str) -> String {
+    KeySequence::parse(keys)
+        .unwrap()
+        .physical(layout)
+        .to_string()
+}
+
+#[test]
+fn first_group_is_active() {
+    let l = layout();
+    assert_eq!(l.name(), "German");
+    assert_eq!(physical(&l, "Ctrl+/"), "Ctrl+Shift+7");
+    assert_eq!(physical(&l, "Ctrl+@"), "Ctrl+AltGr+Q");
+    assert_eq!(physical(&l, "Ctrl+Shift+Minus"), "Ctrl+Shift+Minus");
+}
+
+#[test]
+fn keypad_keys_are_not_keycaps() {
+    let l = layout();
+    assert_eq!(
+        l.keycap('/'),
+        Some(Keycap {
+            key: '7',
+            shift: true,
+            altgr: false,
+        })
+    );
+}
+
+#[test]
+fn set_group_switches_layout() {
+    let mut l = layout();
+    l.set_group(1);
+    assert_eq!(l.name(), "English (US)");
+    assert_eq!(physical(&l, "Ctrl+/"), "Ctrl+/");
+    assert_eq!(l.keycap('@'), None);
+
+    // wraps around, like xkb
+    l.set_group(2);
+    assert_eq!(l.name(), "German");
+}
+
+#[test]
+fn broken_keymaps_are_rejected() {
+    assert!(KeyLayout::from_keymap("xkb_keymap { nonsense }").is_none());
+}
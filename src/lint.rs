@@ -0,0 +1,162 @@
+use std::path::PathBuf;
+
+use crate::shortcut_pack::{duplicate_keys, Diagnostic, Severity};
+use crate::shortcut_resolver::{Layer, ShortcutResolver, ShortcutRoot};
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: orbitkeys lint [--strict] [--resolve APP_ID]… [paths…]
+
+Checks shortcut packs (files or directories). Without paths, lints the
+installed shortcut directories. Exits non-zero on errors, or on warnings
+with --strict. --resolve prints which pack an app_id resolves to and why;
+an app_id that resolves to nothing counts as an error.";
+
+/// `orbitkeys lint [--strict] [--resolve APP_ID]… [paths…]`. Returns whether the packs passed.
+pub fn run(args: &[String]) -> anyhow::Result<bool> {
+    let mut strict = false;
+    let mut paths = Vec::new();
+    let mut app_ids = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strict" => strict = true,
+            "--resolve" => match args.next() {
+                Some(id) => app_ids.push(id.clone()),
+                None => anyhow::bail!("--resolve needs an app_id\n\n{USAGE}"),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(true);
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let roots = if paths.is_empty() {
+        ShortcutRoot::discover()
+    } else {
+        paths
+            .into_iter()
+            .map(|dir| ShortcutRoot {
+                layer: Layer::Project,
+                dir,
+            })
+            .collect()
+    };
+
+    for root in &roots {
+        if !root.dir.exists() {
+            anyhow::bail!("{}: no such file or directory", root.dir.display());
+        }
+    }
+
+    let resolver = ShortcutResolver::new(roots, Vec::new())?;
+
+    let mut diags: Vec<Diagnostic> = resolver.all_diagnostics().into_iter().cloned().collect();
+    diags.extend(binding_conflicts(&resolver));
+    diags.extend(unresolved_nested_packs(&resolver));
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    for d in &diags {
+        match d.severity {
+            Severity::Error => errors += 1,
+            Severity::Warning => warnings += 1,
+        }
+        println!("{d}");
+    }
+
+    for app_id in &app_ids {
+        match resolver.resolve_explained(app_id) {
+            Some(r) => println!("{app_id} -> {} (via {})", r.pack_id, r.rule),
+            None => {
+                errors += 1;
+                println!("{app_id} -> no pack");
+            }
+        }
+    }
+
+    let files: usize = resolver.packs().iter().map(|(_, f)| f.len()).sum();
+    println!("{files} files: {errors} errors, {warnings} warnings");
+
+    Ok(errors == 0 && (!strict || warnings == 0))
+}
+
+/// Keys bound twice in one merged pack by different layers, by the rule of
+/// [`duplicate_keys`]. Duplicates within a file are reported by the
+/// validator, with their lines.
+fn binding_conflicts(resolver: &ShortcutResolver) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+
+    for (id, files) in resolver.packs() {
+        // Broken files are already reported by the validator.
+        let Ok(pack) = resolver.merge(files) else {
+            continue;
+        };
+        let Some(path) = files.iter().rev().find(|f| !f.overlay).map(|f| f.path.clone()) else {
+            continue;
+        };
+
+        let entries = &pack.entries;
+        let bindings = entries
+            .iter()
+            .map(|e| (e.keys.as_str(), e.category.as_deref(), e.layout));
+        for (first, again) in duplicate_keys(bindings) {
+            let (first, again) = (&entries[first], &entries[again]);
+            if first.layer == again.layer {
+                continue;
+            }
+            out.push(Diagnostic {
+                path: path.clone(),
+                line: None,
+                severity: Severity::Warning,
+                message: format!(
+                    "pack \"{id}\": \"{}\" bound to both \"{}\" ({}) and \"{}\" ({})",
+                    again.keys,
+                    first.desc,
+                    first.layer.label(),
+                    again.desc,
+                    again.layer.label()
+                ),
+            });
+        }
+    }
+
+    out
+}
+
+/// Contexts nesting a pack that no file provides.
+fn unresolved_nested_packs(resolver: &ShortcutResolver) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+
+    for (id, files) in resolver.packs() {
+        let Ok(pack) = resolver.merge(files) else {
+            continue;
+        };
+        let Some(path) = files.iter().rev().find(|f| !f.overlay).map(|f| f.path.clone()) else {
+            continue;
+        };
+
+        for ctx in &pack.contexts {
+            let Some(nested) = ctx.pack.as_deref() else {
+                continue;
+            };
+            if resolver.resolve(nested).is_none() {
+                out.push(Diagnostic {
+                    path: path.clone(),
+                    line: None,
+                    severity: Severity::Error,
+                    message: format!(
+                        "pack \"{id}\": context \"{}\" nests \"{nested}\", which has no pack",
+                        ctx.name
+                    ),
+                });
+            }
+        }
+    }
+
+    out
+}
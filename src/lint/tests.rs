@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+
+use super::{binding_conflicts, run};
+use crate::shortcut_resolver::{Layer, ShortcutResolver, ShortcutRoot};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/lint/fixtures")
+        .join(name)
+}
+
+#[test]
+fn bundled_packs_pass_strict() {
+    let bundled = Path::new(env!("CARGO_MANIFEST_DIR")).join("shortcuts");
+    let args = ["--strict".to_string(), bundled.display().to_string()];
+    assert!(run(&args).unwrap());
+}
+
+#[test]
+fn layers_binding_keys_twice_conflict() {
+    let roots = [(Layer::System, "system"), (Layer::User, "user")]
+        .into_iter()
+        .map(|(layer, dir)| ShortcutRoot {
+            layer,
+            dir: fixture(dir),
+        })
+        .collect();
+    let r = ShortcutResolver::with_desktop_dirs(roots, Vec::new(), Vec::new()).unwrap();
+
+    let conflicts = binding_conflicts(&r);
+    // Ctrl+O is spelt the same, so the user's replaces the system's; the
+    // other chord is spelt differently and both stay
+    assert_eq!(conflicts.len(), 1, "{conflicts:?}");
+    assert_eq!(conflicts[0].path, fixture("user/demo.json"));
+    assert_eq!(
+        conflicts[0].message,
+        "pack \"demo\": \"Shift+Ctrl+S\" bound to both \"Save as\" (system) and \"Sync\" (user)"
+    );
+}
@@ -1,40 +1,38 @@
+mod app_keys;
+mod desktop_entries;
 mod focus_watcher;
+mod fuzzy;
+mod importers;
+mod lint;
+mod shortcut_overlay;
+mod shortcut_pack;
 mod shortcut_resolver;
+mod shortcut_watcher;
+mod shutdown;
 mod ui;
 mod key_glyphs;
 mod key_format;
+mod key_layout;
 
 use anyhow::Result;
-use std::path::PathBuf;
 
-use shortcut_resolver::ShortcutResolver;
+use shortcut_resolver::{ShortcutResolver, ShortcutRoot};
+use shutdown::Shutdown;
 
 fn main() -> Result<()> {
-    let shortcuts_path = find_shortcuts_directory();
-    let resolver = ShortcutResolver::new(shortcuts_path)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("lint") {
+        let ok = lint::run(&args[1..])?;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    let resolver = ShortcutResolver::new(ShortcutRoot::discover(), importers::all())?;
+    let shutdown = Shutdown::new()?;
 
     let settings = cosmic::app::Settings::default()
         .is_daemon(false)
         .exit_on_close(true);
 
-    cosmic::app::run::<ui::OrbitKeysUi>(settings, resolver)?;
+    cosmic::app::run::<ui::OrbitKeysUi>(settings, (resolver, shutdown))?;
     Ok(())
 }
-
-fn find_shortcuts_directory() -> PathBuf {
-    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
-        let p = PathBuf::from(dir).join("orbitkeys/shortcuts");
-        if p.exists() {
-            return p;
-        }
-    }
-
-    if let Ok(home) = std::env::var("HOME") {
-        let p = PathBuf::from(home).join(".local/share/orbitkeys/shortcuts");
-        if p.exists() {
-            return p;
-        }
-    }
-
-    PathBuf::from("shortcuts")
-}
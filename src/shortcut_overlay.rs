@@ -0,0 +1,319 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::key_format::{KeyChord, KeySequence};
+use crate::shortcut_pack::{line_of, Diagnostic, Severity};
+use crate::shortcut_resolver::{Layer, LoadedEntry};
+
+#[cfg(test)]
+mod tests;
+
+/// File name suffix marking a user overlay: `code.user.json` patches `code`.
+pub const OVERLAY_SUFFIX: &str = ".user.json";
+
+/// Per-entry patch applied on top of a merged pack.
+///
+/// ```json
+/// {
+///   "prefix": [{ "from": "Ctrl+B", "to": "Ctrl+A" }],
+///   "hide":   [{ "keys": "Ctrl+P" }, { "desc": "Show Command Palette" }],
+///   "rebind": [{ "desc": "Delete line", "to": "Ctrl+D" }],
+///   "add":    [{ "keys": "F1", "desc": "Help", "category": "General" },
+///              { "keys": "Ctrl+K Ctrl+P", "command": "workbench.action.quickOpen" }]
+/// }
+/// ```
+///
+/// An added entry without `desc` takes the description and category of the
+/// entry with the same `command` (even one the overlay hides), so it reads as
+/// another binding for it; one with the keys and command of an existing entry
+/// is skipped.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Overlay {
+    pub prefix: Vec<Prefix>,
+    pub hide: Vec<Selector>,
+    pub rebind: Vec<Rebind>,
+    pub add: Vec<OverlayEntry>,
+}
+
+/// Picks entries by `keys`, `desc`, `command`, or several of them. `keys`
+/// compares the parsed chords, so "Shift+Ctrl+G" selects "Ctrl+Shift+G";
+/// `desc` is case-insensitive.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Selector {
+    #[serde(default)]
+    pub keys: Option<String>,
+    #[serde(default, alias = "description")]
+    pub desc: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// Swaps the first chord of multi-chord sequences, as changing tmux's prefix
+/// does: "Ctrl+B C" becomes "Ctrl+A C".
+#[derive(Debug, Clone, Deserialize)]
+pub struct Prefix {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rebind {
+    #[serde(flatten)]
+    pub selector: Selector,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OverlayEntry {
+    pub keys: String,
+    #[serde(default, alias = "description")]
+    pub desc: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+impl Selector {
+    fn matches(&self, entry: &LoadedEntry) -> bool {
+        if self.keys.is_none() && self.desc.is_none() && self.command.is_none() {
+            return false;
+        }
+
+        let keys_ok = self
+            .keys
+            .as_deref()
+            .is_none_or(|k| same_keys(k, &entry.keys));
+        let desc_ok = self
+            .desc
+            .as_deref()
+            .is_none_or(|d| d.trim().eq_ignore_ascii_case(entry.desc.trim()));
+        let command_ok = self
+            .command
+            .as_deref()
+            .is_none_or(|c| entry.command.as_deref() == Some(c));
+
+        keys_ok && desc_ok && command_ok
+    }
+}
+
+impl Overlay {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let raw =
+            fs::read_to_string(path).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+        serde_json::from_str(&raw).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+    }
+
+    /// Apply prefix, hide, then rebind, then add. Touched entries take the overlay's layer.
+    pub fn apply(&self, entries: &mut Vec<LoadedEntry>, layer: Layer) {
+        // before hiding, so a command whose bindings are replaced keeps its name
+        let named: Vec<_> = entries
+            .iter()
+            .filter(|e| e.command.is_some())
+            .map(|e| (e.command.clone(), e.desc.clone(), e.category.clone()))
+            .collect();
+
+        self.apply_edits(entries, layer);
+
+        let added: Vec<_> = self
+            .add
+            .iter()
+            .filter(|a| {
+                !entries.iter().any(|e| {
+                    a.command.is_some() && e.command == a.command && same_keys(&e.keys, &a.keys)
+                })
+            })
+            .map(|a| {
+                let same = named
+                    .iter()
+                    .find(|(c, ..)| a.command.is_some() && *c == a.command);
+                let desc = match (a.desc.trim(), same, a.command.as_deref()) {
+                    ("", Some((_, desc, _)), _) => desc.clone(),
+                    ("", None, Some(c)) => command_label(c),
+                    (d, ..) => d.to_string(),
+                };
+
+                LoadedEntry {
+                    keys: a.keys.clone(),
+                    desc,
+                    category: a.category.clone().or_else(|| same?.2.clone()),
+                    layer,
+                    layout: None,
+                    command: a.command.clone(),
+                }
+            })
+            .collect();
+        entries.extend(added);
+    }
+
+    /// Prefix, hide and rebind only. Used for title contexts, which `add`
+    /// doesn't target.
+    pub fn apply_edits(&self, entries: &mut Vec<LoadedEntry>, layer: Layer) {
+        for prefix in &self.prefix {
+            for e in entries.iter_mut() {
+                if let Some(keys) = prefix.swap(&e.keys) {
+                    e.keys = keys;
+                    e.layer = layer;
+                }
+            }
+        }
+
+        entries.retain(|e| !self.hide.iter().any(|s| s.matches(e)));
+
+        for rebind in &self.rebind {
+            for e in entries.iter_mut().filter(|e| rebind.selector.matches(e)) {
+                e.keys = rebind.to.clone();
+                e.layer = layer;
+            }
+        }
+    }
+}
+
+impl Prefix {
+    /// `keys` with the prefix swapped, or `None` if it doesn't start with it.
+    fn swap(&self, keys: &str) -> Option<String> {
+        let mut seq = KeySequence::parse(keys).ok()?;
+        let from = KeyChord::parse(&self.from).ok()?;
+        if seq.0.len() < 2 || seq.0[0] != from {
+            return None;
+        }
+        seq.0[0] = KeyChord::parse(&self.to).ok()?;
+        Some(seq.to_string())
+    }
+}
+
+/// The fields each overlay section reads; anything else is a typo serde
+/// would skip.
+const SECTION_FIELDS: &[(&str, &[&str])] = &[
+    ("prefix", &["from", "to"]),
+    ("hide", &["keys", "desc", "description", "command"]),
+    ("rebind", &["keys", "desc", "description", "command", "to"]),
+    (
+        "add",
+        &["keys", "desc", "description", "category", "command"],
+    ),
+];
+
+/// Parse an overlay and report JSON errors, unknown fields and unparseable
+/// key strings.
+pub fn validate_overlay(path: &Path, raw: &str) -> Vec<Diagnostic> {
+    let diag = |line: Option<usize>, severity, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line,
+        severity,
+        message,
+    };
+
+    let overlay: Overlay = match serde_json::from_str(raw) {
+        Ok(o) => o,
+        Err(e) => return vec![diag(Some(e.line()), Severity::Error, e.to_string())],
+    };
+
+    let mut out = Vec::new();
+    let value = serde_json::from_str::<serde_json::Value>(raw).unwrap_or_default();
+    for (field, item) in value.as_object().into_iter().flatten() {
+        let Some((section, known)) = SECTION_FIELDS.iter().find(|(s, _)| s == field) else {
+            out.push(diag(
+                line_of(raw, field),
+                Severity::Warning,
+                format!("unknown field \"{field}\""),
+            ));
+            continue;
+        };
+        let fields = item
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|i| i.as_object());
+        for field in fields
+            .flat_map(|o| o.keys())
+            .filter(|k| !known.contains(&k.as_str()))
+        {
+            out.push(diag(
+                line_of(raw, field),
+                Severity::Warning,
+                format!("unknown field \"{field}\" in {section}"),
+            ));
+        }
+    }
+
+    let keys = overlay
+        .hide
+        .iter()
+        .chain(overlay.rebind.iter().map(|r| &r.selector))
+        .filter_map(|s| s.keys.as_deref())
+        .chain(overlay.rebind.iter().map(|r| r.to.as_str()))
+        .chain(overlay.add.iter().map(|a| a.keys.as_str()))
+        .chain(
+            overlay
+                .prefix
+                .iter()
+                .flat_map(|p| [p.from.as_str(), p.to.as_str()]),
+        );
+
+    let unnamed = overlay
+        .add
+        .iter()
+        .filter(|a| a.desc.trim().is_empty() && a.command.is_none())
+        .map(|a| {
+            let k = &a.keys;
+            diag(
+                line_of(raw, k),
+                Severity::Error,
+                format!("added \"{k}\" needs a desc or a command"),
+            )
+        });
+
+    out.extend(keys.filter_map(|k| {
+        KeySequence::parse(k).err().map(|e| {
+            diag(
+                line_of(raw, k),
+                Severity::Error,
+                format!("keys \"{k}\": {e}"),
+            )
+        })
+    }));
+    out.extend(unnamed);
+    out.sort_by_key(|d| d.line);
+    out
+}
+
+/// The app an overlay file patches, e.g. "code" for `code.user.json`.
+pub fn overlay_target(path: &Path) -> Option<&str> {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .and_then(|name| name.strip_suffix(OVERLAY_SUFFIX))
+}
+
+/// A readable name from the last segment of a command id:
+/// "workbench.action.quickOpen" -> "Quick open",
+/// "editor:toggle-bold" -> "Toggle bold". Commands with arguments, like
+/// tmux's "split-window -h", are shown as written.
+fn command_label(command: &str) -> String {
+    if command.trim().contains(char::is_whitespace) {
+        return command.trim().to_string();
+    }
+    let last = command.rsplit(['.', ':', '/']).next().unwrap_or(command);
+    let mut out = String::new();
+    for c in last.chars() {
+        match c {
+            '-' | '_' => out.push(' '),
+            _ if out.is_empty() => out.extend(c.to_uppercase()),
+            _ if c.is_uppercase() => {
+                out.push(' ');
+                out.extend(c.to_lowercase());
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn same_keys(a: &str, b: &str) -> bool {
+    match (KeySequence::parse(a), KeySequence::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.trim() == b.trim(),
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use super::{command_label, same_keys, validate_overlay, Overlay};
+use crate::shortcut_pack::Severity;
+use crate::shortcut_resolver::{Layer, LoadedEntry};
+
+fn entry(keys: &str, desc: &str, command: Option<&str>) -> LoadedEntry {
+    LoadedEntry {
+        keys: keys.to_string(),
+        desc: desc.to_string(),
+        category: Some("General".to_string()),
+        layer: Layer::System,
+        layout: None,
+        command: command.map(str::to_string),
+    }
+}
+
+/// `entries` after applying the overlay in `raw` from the user layer, as
+/// "keys: desc" with a "*" on entries the overlay touched.
+fn applied(raw: &str, entries: &[LoadedEntry]) -> Vec<String> {
+    let overlay: Overlay = serde_json::from_str(raw).unwrap();
+    let mut entries = entries.to_vec();
+    overlay.apply(&mut entries, Layer::User);
+    entries
+        .iter()
+        .map(|e| {
+            let touched = if e.layer == Layer::User { "*" } else { "" };
+            format!("{}: {}{touched}", e.keys, e.desc)
+        })
+        .collect()
+}
+
+#[test]
+fn prefix_swaps_the_first_chord_of_sequences() {
+    let entries = [
+        entry("Ctrl+B C", "New window", None),
+        entry("ctrl+b %", "Split", None),
+        entry("Ctrl+B", "Bold", None),
+        entry("Ctrl+X Ctrl+B", "Buffers", None),
+    ];
+    assert_eq!(
+        applied(
+            r#"{ "prefix": [{ "from": "Ctrl+B", "to": "Ctrl+A" }] }"#,
+            &entries
+        ),
+        [
+            "Ctrl+A C: New window*",
+            "Ctrl+A %: Split*",
+            "Ctrl+B: Bold",
+            "Ctrl+X Ctrl+B: Buffers",
+        ]
+    );
+}
+
+#[test]
+fn hide_selects_by_every_given_field() {
+    let entries = [
+        entry("Ctrl+Shift+G", "Source control", None),
+        entry("Ctrl+S", "Save", Some("save")),
+        entry("Ctrl+Alt+S", "Save all", Some("saveAll")),
+        entry("Ctrl+P", "Go to file", Some("quickOpen")),
+        entry("Ctrl+E", "Go to file", Some("quickOpen")),
+    ];
+    let raw = r#"{ "hide": [
+        { "keys": "Shift+Ctrl+G" },
+        { "description": " SAVE " },
+        { "keys": "Ctrl+E", "command": "quickOpen" },
+        { "keys": "Ctrl+Alt+S", "command": "save" },
+        {}
+    ] }"#;
+    assert_eq!(
+        applied(raw, &entries),
+        ["Ctrl+Alt+S: Save all", "Ctrl+P: Go to file"]
+    );
+}
+
+#[test]
+fn rebind_moves_every_match() {
+    let entries = [
+        entry("Ctrl+Shift+K", "Delete line", Some("deleteLines")),
+        entry("Ctrl+X", "Cut", None),
+        entry("Ctrl+Y", "Delete line", Some("deleteLines")),
+    ];
+    assert_eq!(
+        applied(
+            r#"{ "rebind": [{ "command": "deleteLines", "to": "Ctrl+D" }] }"#,
+            &entries
+        ),
+        [
+            "Ctrl+D: Delete line*",
+            "Ctrl+X: Cut",
+            "Ctrl+D: Delete line*"
+        ]
+    );
+}
+
+#[test]
+fn prefix_then_hide_then_rebind_then_add() {
+    let entries = [
+        entry("Ctrl+B C", "New window", None),
+        entry("Ctrl+B D", "Detach", None),
+        entry("Ctrl+B X", "Kill pane", None),
+    ];
+    // hide and rebind see the swapped prefix; rebind can't bring back what
+    // hide removed; add sees the rebound keys
+    let raw = r#"{
+        "prefix": [{ "from": "Ctrl+B", "to": "Ctrl+A" }],
+        "hide": [{ "keys": "Ctrl+A D" }, { "keys": "Ctrl+B X" }],
+        "rebind": [
+            { "keys": "Ctrl+A D", "to": "F12" },
+            { "keys": "Ctrl+A C", "to": "Ctrl+T" }
+        ],
+        "add": [{ "keys": "Ctrl+A C", "desc": "Still free" }]
+    }"#;
+    assert_eq!(
+        applied(raw, &entries),
+        [
+            "Ctrl+T: New window*",
+            "Ctrl+A X: Kill pane*",
+            "Ctrl+A C: Still free*",
+        ]
+    );
+}
+
+#[test]
+fn added_commands_take_the_name_of_hidden_ones() {
+    let mut quick_open = entry("Ctrl+P", "Go to file", Some("workbench.action.quickOpen"));
+    quick_open.category = Some("Navigation".to_string());
+    let raw = r#"{
+        "hide": [{ "keys": "Ctrl+P" }],
+        "add": [
+            { "keys": "Ctrl+E", "command": "workbench.action.quickOpen" },
+            { "keys": "Ctrl+T", "command": "workbench.action.quickOpen",
+              "desc": "Open anything", "category": "Mine" },
+            { "keys": "F1", "command": "editor:toggle-bold" }
+        ]
+    }"#;
+    let overlay: Overlay = serde_json::from_str(raw).unwrap();
+    let mut entries = vec![quick_open];
+    overlay.apply(&mut entries, Layer::User);
+
+    let found: Vec<_> = entries
+        .iter()
+        .map(|e| (e.keys.as_str(), e.desc.as_str(), e.category.as_deref()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("Ctrl+E", "Go to file", Some("Navigation")),
+            ("Ctrl+T", "Open anything", Some("Mine")),
+            ("F1", "Toggle bold", None),
+        ]
+    );
+    assert!(entries.iter().all(|e| e.layer == Layer::User));
+}
+
+#[test]
+fn added_bindings_already_present_are_skipped() {
+    let entries = [entry("Ctrl+Shift+P", "Palette", Some("showCommands"))];
+    let raw = r#"{ "add": [
+        { "keys": "Shift+Ctrl+P", "command": "showCommands" },
+        { "keys": "Ctrl+Shift+P", "command": "other", "desc": "Other" },
+        { "keys": "F1", "command": "showCommands" }
+    ] }"#;
+    assert_eq!(
+        applied(raw, &entries),
+        [
+            "Ctrl+Shift+P: Palette",
+            "Ctrl+Shift+P: Other*",
+            "F1: Palette*",
+        ]
+    );
+}
+
+#[test]
+fn command_labels() {
+    assert_eq!(command_label("workbench.action.quickOpen"), "Quick open");
+    assert_eq!(command_label("editor:toggle-bold"), "Toggle bold");
+    assert_eq!(command_label("app/save_all"), "Save all");
+    assert_eq!(command_label("copy"), "Copy");
+    // commands with arguments stay as written
+    assert_eq!(command_label(" split-window -h "), "split-window -h");
+}
+
+#[test]
+fn same_keys_compares_chords() {
+    assert!(same_keys("Shift+Ctrl+G", "Ctrl+Shift+G"));
+    assert!(same_keys("ctrl+b c", "Ctrl+B C"));
+    assert!(!same_keys("Ctrl+G", "Ctrl+Shift+G"));
+    assert!(!same_keys("Ctrl+B C", "Ctrl+B"));
+    // unparseable strings only match themselves
+    assert!(same_keys(" Ctrl+Nope ", "Ctrl+Nope"));
+    assert!(!same_keys("Ctrl+Nope", "Ctrl+Nop"));
+}
+
+#[test]
+fn validation_warns_about_unknown_fields() {
+    let raw = r#"{
+  "hide": [{ "keys": "Ctrl+P", "hidden": true }],
+  "rebind": [{ "desc": "Delete line", "rebnd": "Ctrl+D", "to": "Ctrl+D" }],
+  "ad": [],
+  "add": [{ "keys": "Ctrl+Nope", "desc": "Broken" }, { "keys": "F1" }]
+}"#;
+    let found: Vec<_> = validate_overlay(Path::new("code.user.json"), raw)
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        found,
+        [
+            "code.user.json:2: warning: unknown field \"hidden\" in hide",
+            "code.user.json:3: warning: unknown field \"rebnd\" in rebind",
+            "code.user.json:4: warning: unknown field \"ad\"",
+            "code.user.json:5: error: keys \"Ctrl+Nope\": unknown key \"Nope\"",
+            "code.user.json:5: error: added \"F1\" needs a desc or a command",
+        ]
+    );
+}
+
+#[test]
+fn overlays_that_dont_parse_are_errors() {
+    let diags = validate_overlay(Path::new("code.user.json"), "{\n  \"hide\": {}\n}");
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].line, Some(2));
+    assert_eq!(diags[0].severity, Severity::Error);
+}
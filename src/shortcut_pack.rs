@@ -0,0 +1,478 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::key_format::KeySequence;
+
+#[cfg(test)]
+mod tests;
+
+/// A shortcut pack as stored in `shortcuts/<app>.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShortcutPack {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub app_id: Option<String>,
+    /// Other app_ids this pack answers to (`"app_ids"` in JSON).
+    #[serde(default, rename = "app_ids", alias = "aliases")]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Add to the pack below in the layer stack instead of replacing it.
+    #[serde(default)]
+    pub extends: bool,
+    /// Display order for categories; unlisted ones follow alphabetically.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub shortcuts: Vec<Shortcut>,
+    /// Shortcut sets that apply while the focused window's title matches.
+    #[serde(default)]
+    pub contexts: Vec<Context>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Shortcut {
+    pub keys: String,
+    #[serde(alias = "description")]
+    pub desc: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Only applies on tiling workspaces (COSMIC desktop pack).
+    #[serde(default)]
+    pub tiling_only: bool,
+    /// Only applies on floating workspaces.
+    #[serde(default)]
+    pub floating_only: bool,
+    /// The app's own id for the action (e.g. VS Code's
+    /// `workbench.action.quickOpen`), so imported keybindings can remove it.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// Workspace layout a shortcut is limited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    Tiling,
+    Floating,
+}
+
+impl Shortcut {
+    /// `None` when the entry applies everywhere. Setting both flags is
+    /// reported by the validator and treated as neither.
+    pub fn layout(&self) -> Option<Layout> {
+        match (self.tiling_only, self.floating_only) {
+            (true, false) => Some(Layout::Tiling),
+            (false, true) => Some(Layout::Floating),
+            _ => None,
+        }
+    }
+}
+
+/// A title-dependent shortcut set, e.g. Google Docs inside Firefox:
+///
+/// ```json
+/// { "name": "Google Docs", "title_matches": "— Google Docs", "shortcuts": [...] }
+/// ```
+///
+/// A context can also nest another app's pack, e.g. tmux inside a terminal:
+///
+/// ```json
+/// { "name": "tmux", "title_matches": "^tmux\\b", "pack": "tmux" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Context {
+    pub name: String,
+    /// Regex searched for in the window title.
+    pub title_matches: String,
+    /// Show only this context's shortcuts instead of adding to the pack's.
+    #[serde(default)]
+    pub replace: bool,
+    #[serde(default)]
+    pub shortcuts: Vec<Shortcut>,
+    /// App id of a pack whose shortcuts are shown too while this context
+    /// applies, under categories prefixed with the context's name.
+    #[serde(default)]
+    pub pack: Option<String>,
+}
+
+const PACK_FIELDS: &[&str] = &[
+    "name",
+    "app_id",
+    "app_ids",
+    "aliases",
+    "version",
+    "author",
+    "extends",
+    "categories",
+    "shortcuts",
+    "contexts",
+];
+
+const SHORTCUT_FIELDS: &[&str] = &[
+    "keys",
+    "desc",
+    "description",
+    "category",
+    "tiling_only",
+    "floating_only",
+    "command",
+];
+
+const CONTEXT_FIELDS: &[&str] = &["name", "title_matches", "replace", "shortcuts", "pack"];
+
+impl ShortcutPack {
+    pub fn parse(raw: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(raw)
+    }
+
+    /// Every app_id this pack claims, primary id first.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.app_id
+            .as_deref()
+            .into_iter()
+            .chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// First line of `raw` containing `"needle"`, for diagnostics on files too
+/// small or too loose to scan properly.
+pub fn line_of(raw: &str, needle: &str) -> Option<usize> {
+    let quoted = format!("\"{needle}\"");
+    raw.lines().position(|l| l.contains(&quoted)).map(|i| i + 1)
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        let sev = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, ": {sev}: {}", self.message)
+    }
+}
+
+/// Parse `raw` and check it: unknown fields, duplicate keys, empty descriptions
+/// and unparseable key strings, in contexts too. Returns the pack if it
+/// deserialized at all.
+pub fn validate_pack(path: &Path, raw: &str) -> (Option<ShortcutPack>, Vec<Diagnostic>) {
+    let mut out = Vec::new();
+    let diag = |line: Option<usize>, severity, message: String| Diagnostic {
+        path: path.to_path_buf(),
+        line,
+        severity,
+        message,
+    };
+
+    let pack = match ShortcutPack::parse(raw) {
+        Ok(p) => p,
+        Err(e) => {
+            out.push(diag(Some(e.line()), Severity::Error, e.to_string()));
+            return (None, out);
+        }
+    };
+
+    let loc = Locations::scan(raw);
+    let value = serde_json::from_str::<serde_json::Value>(raw).unwrap_or_default();
+
+    if let Some(top) = value.as_object() {
+        for field in top.keys().filter(|k| !PACK_FIELDS.contains(&k.as_str())) {
+            out.push(diag(
+                loc.top_keys.get(field).copied(),
+                Severity::Warning,
+                format!("unknown field \"{field}\""),
+            ));
+        }
+    }
+
+    let findings = check_shortcuts(&pack.shortcuts, &value["shortcuts"], &loc.entries);
+    for (line, severity, message) in findings {
+        out.push(diag(line, severity, message));
+    }
+
+    for (i, ctx) in pack.contexts.iter().enumerate() {
+        let line = loc.context_line(i);
+        let raw_ctx = &value["contexts"][i];
+
+        if let Some(obj) = raw_ctx.as_object() {
+            for field in obj.keys().filter(|k| !CONTEXT_FIELDS.contains(&k.as_str())) {
+                out.push(diag(
+                    line,
+                    Severity::Warning,
+                    format!("unknown field \"{field}\" in context"),
+                ));
+            }
+        }
+
+        if let Err(e) = regex::Regex::new(&ctx.title_matches) {
+            // regex errors are multi-line with a caret diagram; keep the summary
+            let msg = e.to_string();
+            let summary = msg.lines().last().unwrap_or_default();
+            out.push(diag(
+                line,
+                Severity::Error,
+                format!(
+                    "context \"{}\": bad title_matches \"{}\": {}",
+                    ctx.name,
+                    ctx.title_matches,
+                    summary.trim_start_matches("error: ")
+                ),
+            ));
+        }
+        if ctx.shortcuts.is_empty() && ctx.pack.is_none() {
+            out.push(diag(
+                line,
+                Severity::Warning,
+                format!("context \"{}\" has no shortcuts or pack", ctx.name),
+            ));
+        }
+
+        let lines = loc.context_entries.get(i).map_or(&[][..], Vec::as_slice);
+        let findings = check_shortcuts(&ctx.shortcuts, &raw_ctx["shortcuts"], lines);
+        for (line, severity, message) in findings {
+            out.push(diag(
+                line,
+                severity,
+                format!("context \"{}\": {message}", ctx.name),
+            ));
+        }
+    }
+
+    (Some(pack), out)
+}
+
+/// The checks every list of shortcuts gets, top-level or in a context.
+/// `raw` is the list as JSON and `lines` where its entries start.
+fn check_shortcuts(
+    shortcuts: &[Shortcut],
+    raw: &serde_json::Value,
+    lines: &[usize],
+) -> Vec<(Option<usize>, Severity, String)> {
+    let mut out = Vec::new();
+    let line = |i: usize| lines.get(i).copied();
+
+    for (i, item) in raw.as_array().into_iter().flatten().enumerate() {
+        let Some(obj) = item.as_object() else { continue };
+        for field in obj.keys().filter(|k| !SHORTCUT_FIELDS.contains(&k.as_str())) {
+            out.push((
+                line(i),
+                Severity::Warning,
+                format!("unknown field \"{field}\" in shortcut"),
+            ));
+        }
+    }
+
+    let bindings = shortcuts
+        .iter()
+        .map(|s| (s.keys.as_str(), s.category.as_deref(), s.layout()));
+    for (first, again) in duplicate_keys(bindings) {
+        let first_line = line(first)
+            .map(|l| format!(" (first on line {l})"))
+            .unwrap_or_default();
+        out.push((
+            line(again),
+            Severity::Warning,
+            format!("duplicate keys \"{}\"{first_line}", shortcuts[again].keys),
+        ));
+    }
+
+    for (i, s) in shortcuts.iter().enumerate() {
+        if s.tiling_only && s.floating_only {
+            out.push((
+                line(i),
+                Severity::Warning,
+                format!(
+                    "\"{}\" is both tiling_only and floating_only; showing it everywhere",
+                    s.keys
+                ),
+            ));
+        }
+
+        if s.desc.trim().is_empty() {
+            out.push((
+                line(i),
+                Severity::Warning,
+                format!("\"{}\" has an empty description", s.keys),
+            ));
+        }
+
+        if let Err(e) = KeySequence::parse(&s.keys) {
+            out.push((
+                line(i),
+                Severity::Error,
+                format!("keys \"{}\": {e}", s.keys),
+            ));
+        }
+    }
+
+    out.sort_by_key(|(line, ..)| *line);
+    out
+}
+
+/// Bindings of the same keys that clash, as `(first, again)` indices into
+/// `bindings` of `(keys, category, layout)`. The same chord may do different
+/// things in different categories (Ctrl+C in the editor and in its terminal)
+/// or on different layouts. Keys that don't parse are skipped.
+pub fn duplicate_keys<'a>(
+    bindings: impl IntoIterator<Item = (&'a str, Option<&'a str>, Option<Layout>)>,
+) -> Vec<(usize, usize)> {
+    let bindings: Vec<_> = bindings
+        .into_iter()
+        .map(|(keys, category, layout)| (KeySequence::parse(keys).ok(), category, layout))
+        .collect();
+
+    let mut out = Vec::new();
+    for (i, (seq, category, layout)) in bindings.iter().enumerate() {
+        let Some(seq) = seq else { continue };
+        let first = bindings[..i].iter().position(|(s, c, l)| {
+            s.as_ref() == Some(seq)
+                && c == category
+                && (l.is_none() || layout.is_none() || l == layout)
+        });
+        if let Some(first) = first {
+            out.push((first, i));
+        }
+    }
+    out
+}
+
+/// Line numbers of top-level keys and of each object in the `shortcuts` and
+/// `contexts` arrays, and in each context's `shortcuts`. serde_json doesn't
+/// keep spans, so this is a small scan over the raw text.
+struct Locations {
+    top_keys: HashMap<String, usize>,
+    entries: Vec<usize>,
+    contexts: Vec<usize>,
+    /// Per context, its shortcuts.
+    context_entries: Vec<Vec<usize>>,
+}
+
+/// An array whose objects `Locations` records.
+#[derive(Clone, Copy)]
+enum List {
+    Shortcuts,
+    Contexts,
+    /// The `shortcuts` of the context with this index.
+    ContextShortcuts(usize),
+}
+
+impl Locations {
+    fn scan(raw: &str) -> Self {
+        let mut top_keys = HashMap::new();
+        let mut entries = Vec::new();
+        let mut contexts = Vec::new();
+        let mut context_entries: Vec<Vec<usize>> = Vec::new();
+
+        let mut line = 1;
+        let mut depth = 0usize;
+        // arrays being tracked, innermost last, with the depth of their items
+        let mut lists: Vec<(usize, List)> = Vec::new();
+        let mut last_str: Option<String> = None;
+        let mut key: Option<String> = None;
+
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => line += 1,
+                '"' => {
+                    let mut s = String::new();
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => s.push(c),
+                        }
+                    }
+                    last_str = Some(s);
+                }
+                ':' => {
+                    key = last_str.take();
+                    if depth == 1 {
+                        if let Some(k) = &key {
+                            top_keys.entry(k.clone()).or_insert(line);
+                        }
+                    }
+                }
+                '[' | '{' => {
+                    let list = lists.last().filter(|(d, _)| *d == depth).map(|(_, l)| *l);
+                    if c == '{' {
+                        match list {
+                            Some(List::Shortcuts) => entries.push(line),
+                            Some(List::Contexts) => {
+                                contexts.push(line);
+                                context_entries.push(Vec::new());
+                            }
+                            Some(List::ContextShortcuts(i)) => context_entries[i].push(line),
+                            None => {}
+                        }
+                    }
+                    if c == '[' {
+                        // a context's own keys are one level below its opening brace
+                        let in_context = lists
+                            .last()
+                            .is_some_and(|(d, l)| matches!(l, List::Contexts) && *d + 1 == depth);
+                        let opened = match key.as_deref() {
+                            Some("shortcuts") if depth == 1 => Some(List::Shortcuts),
+                            Some("contexts") if depth == 1 => Some(List::Contexts),
+                            Some("shortcuts") if in_context => {
+                                Some(List::ContextShortcuts(contexts.len() - 1))
+                            }
+                            _ => None,
+                        };
+                        if let Some(l) = opened {
+                            lists.push((depth + 1, l));
+                        }
+                    }
+                    depth += 1;
+                    key = None;
+                }
+                ']' | '}' => {
+                    if c == ']' && lists.last().is_some_and(|(d, _)| *d == depth) {
+                        lists.pop();
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                ',' => {
+                    last_str = None;
+                    key = None;
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            top_keys,
+            entries,
+            contexts,
+            context_entries,
+        }
+    }
+
+    fn context_line(&self, i: usize) -> Option<usize> {
+        self.contexts.get(i).copied()
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::Path;
+
+use super::{validate_pack, Locations, Severity};
+
+/// The findings for `raw` as "line: message", warnings and errors alike.
+fn check(raw: &str) -> Vec<String> {
+    let (pack, diags) = validate_pack(Path::new("test.json"), raw);
+    assert!(pack.is_some(), "{diags:?}");
+    diags
+        .iter()
+        .map(|d| format!("{}: {}", d.line.unwrap_or(0), d.message))
+        .collect()
+}
+
+const NESTED: &str = r#"{
+  "app_id": "demo",
+  "categories": ["General", ["odd", {"nested": "array"}]],
+  "shortcuts": [
+    { "keys": "Ctrl+A", "desc": "Say \"hi\" [x] {y}" },
+    { "keys": "Ctrl+B", "desc": "B",
+      "note": ["a", {"b": 1}] },
+    { "keys": "Ctrl+C", "desc": "C" }
+  ],
+  "contexts": [
+    { "name": "one", "title_matches": "one",
+      "shortcuts": [
+        { "keys": "Ctrl+D", "desc": "D" },
+        { "keys": "Ctrl+E", "desc": "E" }
+      ]
+    },
+    { "name": "two", "title_matches": "two", "pack": "other" },
+    {
+      "name": "three",
+      "title_matches": "three",
+      "shortcuts": [{ "keys": "Ctrl+F", "desc": "F" }]
+    }
+  ]
+}"#;
+
+#[test]
+fn scan_finds_entries_past_strings_and_nested_arrays() {
+    let loc = Locations::scan(NESTED);
+
+    assert_eq!(loc.top_keys["app_id"], 2);
+    assert_eq!(loc.top_keys["shortcuts"], 4);
+    assert_eq!(loc.top_keys["contexts"], 10);
+    // keys of nested objects aren't top-level
+    assert!(!loc.top_keys.contains_key("nested"));
+    assert!(!loc.top_keys.contains_key("keys"));
+
+    assert_eq!(loc.entries, [5, 6, 8]);
+    assert_eq!(loc.contexts, [11, 17, 18]);
+    assert_eq!(loc.context_entries, [vec![13, 14], vec![], vec![21]]);
+}
+
+#[test]
+fn unknown_fields_are_warned_where_they_are() {
+    let raw = r#"{
+  "app_id": "demo",
+  "colour": "blue",
+  "shortcuts": [
+    { "keys": "Ctrl+A", "desc": "A" },
+    { "keys": "Ctrl+B", "desc": "B", "hidden": true }
+  ],
+  "contexts": [
+    { "name": "docs", "title_matches": "Docs", "replce": true,
+      "shortcuts": [
+        { "keys": "Ctrl+C", "desc": "C" },
+        { "keys": "Ctrl+D", "desc": "D", "tiling": true }
+      ]
+    }
+  ]
+}"#;
+    assert_eq!(
+        check(raw),
+        [
+            "3: unknown field \"colour\"",
+            "6: unknown field \"hidden\" in shortcut",
+            "9: unknown field \"replce\" in context",
+            "12: context \"docs\": unknown field \"tiling\" in shortcut",
+        ]
+    );
+}
+
+#[test]
+fn duplicates_may_differ_by_layout() {
+    let raw = r#"{
+  "shortcuts": [
+    { "keys": "Super+Y", "desc": "Enable tiling", "floating_only": true },
+    { "keys": "Super+Y", "desc": "Disable tiling", "tiling_only": true },
+    { "keys": "Super+G", "desc": "Floating toggle", "tiling_only": true },
+    { "keys": "Super+G", "desc": "Float everywhere" },
+    { "keys": "Super+Q", "desc": "Close" },
+    { "keys": "super+q", "desc": "Close again" }
+  ]
+}"#;
+    assert_eq!(
+        check(raw),
+        [
+            "6: duplicate keys \"Super+G\" (first on line 5)",
+            "8: duplicate keys \"super+q\" (first on line 7)",
+        ]
+    );
+}
+
+#[test]
+fn duplicates_may_differ_by_category() {
+    let raw = r#"{
+  "shortcuts": [
+    { "keys": "Ctrl+C", "desc": "Copy line", "category": "Editing" },
+    { "keys": "Ctrl+C", "desc": "Copy selection", "category": "Terminal" },
+    { "keys": "Ctrl+C", "desc": "Copy", "category": "Editing" },
+    { "keys": "Ctrl+V", "desc": "Paste" },
+    { "keys": "Ctrl+V", "desc": "Paste again" }
+  ]
+}"#;
+    assert_eq!(
+        check(raw),
+        [
+            "5: duplicate keys \"Ctrl+C\" (first on line 3)",
+            "7: duplicate keys \"Ctrl+V\" (first on line 6)",
+        ]
+    );
+}
+
+#[test]
+fn both_layout_flags_are_warned() {
+    let raw = r#"{
+  "shortcuts": [
+    { "keys": "Super+Y", "desc": "Toggle tiling", "tiling_only": true, "floating_only": true }
+  ]
+}"#;
+    assert_eq!(
+        check(raw),
+        ["3: \"Super+Y\" is both tiling_only and floating_only; showing it everywhere"]
+    );
+}
+
+#[test]
+fn context_entries_get_every_check() {
+    let raw = r#"{
+  "shortcuts": [{ "keys": "Ctrl+K", "desc": "Outside" }],
+  "contexts": [
+    {
+      "name": "docs",
+      "title_matches": "Docs",
+      "shortcuts": [
+        { "keys": "Ctrl+K", "desc": "Insert link" },
+        { "keys": "Ctrl+K", "desc": "Again" },
+        { "keys": "Ctrl+L", "desc": " " },
+        { "keys": "Ctrl+Nope", "desc": "Broken" },
+        { "keys": "Ctrl+M", "desc": "Both", "tiling_only": true, "floating_only": true }
+      ]
+    }
+  ]
+}"#;
+    assert_eq!(
+        check(raw),
+        [
+            "9: context \"docs\": duplicate keys \"Ctrl+K\" (first on line 8)",
+            "10: context \"docs\": \"Ctrl+L\" has an empty description",
+            "11: context \"docs\": keys \"Ctrl+Nope\": unknown key \"Nope\"",
+            "12: context \"docs\": \"Ctrl+M\" is both tiling_only and floating_only; showing it everywhere",
+        ]
+    );
+}
+
+#[test]
+fn bad_contexts_are_reported_at_their_start() {
+    let raw = r#"{
+  "contexts": [
+    { "name": "empty", "title_matches": "x" },
+    { "name": "broken", "title_matches": "(", "pack": "tmux" }
+  ]
+}"#;
+    let (_, diags) = validate_pack(Path::new("test.json"), raw);
+    assert_eq!(diags.len(), 2, "{diags:?}");
+    assert_eq!(diags[0].line, Some(3));
+    assert_eq!(diags[0].severity, Severity::Warning);
+    assert_eq!(diags[1].line, Some(4));
+    assert_eq!(diags[1].severity, Severity::Error);
+    assert!(diags[1]
+        .message
+        .starts_with("context \"broken\": bad title_matches \"(\""));
+}
+
+#[test]
+fn syntax_errors_have_a_line() {
+    let (pack, diags) = validate_pack(Path::new("test.json"), "{\n  \"shortcuts\": [\n    {]\n}");
+    assert!(pack.is_none());
+    assert_eq!(diags[0].line, Some(3));
+    assert_eq!(diags[0].severity, Severity::Error);
+}
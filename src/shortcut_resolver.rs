@@ -1,75 +1,944 @@
-use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use regex::Regex;
+
+use crate::desktop_entries::{self, DesktopEntry};
+use crate::importers::{Imported, ImportedContent, Importer};
+use crate::shortcut_overlay::{self, Overlay};
+use crate::shortcut_pack::{self, Diagnostic, Layout, Severity, Shortcut, ShortcutPack};
+use crate::shortcut_watcher::WatchSet;
+
+#[cfg(test)]
+mod tests;
+
+/// App id of the desktop pack, shown while no window has focus.
+pub const DESKTOP_APP_ID: &str = "root";
+
+/// Where a shortcut root lives. Later variants take precedence over earlier ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /// `$XDG_DATA_DIRS/orbitkeys/shortcuts` (distro packages)
+    System,
+    /// `$XDG_DATA_HOME/orbitkeys/shortcuts`
+    User,
+    /// `$ORBITKEYS_SHORTCUTS_PATH`, or `./shortcuts` when nothing else exists
+    Project,
+}
+
+impl Layer {
+    pub fn label(self) -> &'static str {
+        match self {
+            Layer::System => "system",
+            Layer::User => "user",
+            Layer::Project => "project",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShortcutRoot {
+    pub layer: Layer,
+    /// Usually a directory; a single pack file is accepted too (`orbitkeys lint`).
+    pub dir: PathBuf,
+}
+
+impl ShortcutRoot {
+    /// Collect the existing shortcut roots, lowest precedence first.
+    pub fn discover() -> Vec<ShortcutRoot> {
+        let mut roots = Vec::new();
+
+        // XDG_DATA_DIRS lists the most important dir first; we want it last.
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        for dir in std::env::split_paths(&data_dirs)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            roots.push(ShortcutRoot {
+                layer: Layer::System,
+                dir: dir.join("orbitkeys/shortcuts"),
+            });
+        }
+
+        let data_home = std::env::var("XDG_DATA_HOME")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var("HOME")
+                    .ok()
+                    .map(|h| PathBuf::from(h).join(".local/share"))
+            });
+        if let Some(dir) = data_home {
+            roots.push(ShortcutRoot {
+                layer: Layer::User,
+                dir: dir.join("orbitkeys/shortcuts"),
+            });
+        }
+
+        if let Ok(list) = std::env::var("ORBITKEYS_SHORTCUTS_PATH") {
+            for dir in std::env::split_paths(&list)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+            {
+                roots.push(ShortcutRoot {
+                    layer: Layer::Project,
+                    dir,
+                });
+            }
+        }
+
+        roots.retain(|r| r.dir.is_dir());
+
+        // Running from a checkout with nothing installed.
+        if roots.is_empty() {
+            roots.push(ShortcutRoot {
+                layer: Layer::Project,
+                dir: PathBuf::from("shortcuts"),
+            });
+        }
+
+        roots
+    }
+}
+
+/// One JSON file contributing to a pack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackFile {
+    pub layer: Layer,
+    pub path: PathBuf,
+    /// `<app>.user.json` patch rather than a full pack
+    pub overlay: bool,
+}
+
+/// A shortcut with the layer of the file it came from.
+#[derive(Clone, Debug)]
+pub struct LoadedEntry {
+    pub keys: String,
+    pub desc: String,
+    pub category: Option<String>,
+    pub layer: Layer,
+    pub layout: Option<Layout>,
+    pub command: Option<String>,
+}
+
+impl LoadedEntry {
+    fn from_shortcut(s: &Shortcut, layer: Layer) -> Self {
+        Self {
+            keys: s.keys.clone(),
+            desc: s.desc.clone(),
+            category: s.category.clone(),
+            layer,
+            layout: s.layout(),
+            command: s.command.clone(),
+        }
+    }
+
+    /// Whether the entry applies on a workspace with `layout`; everything
+    /// does while the layout is unknown.
+    pub fn applies_to(&self, layout: Option<Layout>) -> bool {
+        match (self.layout, layout) {
+            (Some(only), Some(current)) => only == current,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LoadedPack {
+    pub name: Option<String>,
+    pub categories: Vec<String>,
+    pub files: Vec<PackFile>,
+    pub entries: Vec<LoadedEntry>,
+    pub contexts: Vec<LoadedContext>,
+}
+
+/// A pack context with its title pattern compiled.
+#[derive(Clone, Debug)]
+pub struct LoadedContext {
+    pub name: String,
+    pub title: Regex,
+    pub replace: bool,
+    /// App id of the nested pack, whose entries are part of `entries`.
+    pub pack: Option<String>,
+    /// Made from a title hint rather than declared by the pack.
+    pub hinted: bool,
+    pub entries: Vec<LoadedEntry>,
+}
+
+impl LoadedPack {
+    /// Contexts whose pattern matches `title`, in pack order.
+    pub fn active_contexts(&self, title: &str) -> Vec<&LoadedContext> {
+        self.contexts
+            .iter()
+            .filter(|c| c.title.is_match(title))
+            .collect()
+    }
+
+    /// Entries shown for a window titled `title`. Matching contexts add their
+    /// shortcuts (same keys win over the pack's) or, with `replace`, stand alone.
+    pub fn entries_for_title(&self, title: &str) -> Vec<LoadedEntry> {
+        let mut entries = self.entries.clone();
+
+        for ctx in self.active_contexts(title) {
+            if ctx.replace {
+                entries.clear();
+            }
+            for e in &ctx.entries {
+                match entries.iter_mut().find(|x| x.keys == e.keys) {
+                    Some(existing) => *existing = e.clone(),
+                    None => entries.push(e.clone()),
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// Replace the contexts made from title hints with `hinted`.
+    pub fn set_hinted(&mut self, hinted: Vec<LoadedContext>) {
+        self.contexts.retain(|c| !c.hinted);
+        self.contexts.extend(hinted);
+    }
+}
+
+/// Title word asking for another app's pack, as in "~/src orbitkeys:htop".
+/// A shell or program sets it with the OSC 2 title sequence.
+pub const TITLE_HINT_PREFIX: &str = "orbitkeys:";
+
+/// Fuzzy matches must score above this; sharing one of two tokens, as
+/// "gnome-terminal" and "cosmic-terminal" do, is not enough.
+const FUZZY_THRESHOLD: f32 = 0.5;
+
+/// Reverse-DNS prefixes that say nothing about which app it is.
+const GENERIC_TOKENS: &[&str] = &["org", "com", "net", "io", "dev", "app", "desktop"];
+
+/// Which resolution stage produced a match.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatchRule {
+    /// The app_id is a pack's id, file name or one of its `app_ids`.
+    ExactAlias,
+    /// Last segment of a reverse-DNS id, e.g. "firefox" from "org.mozilla.firefox".
+    ReverseDnsSegment(String),
+    /// An installed desktop file has the app_id as its ID, `StartupWMClass` or
+    /// `X-Flatpak` (`matched_on`), and another of its fields names a pack.
+    DesktopEntry {
+        desktop_id: String,
+        matched_on: &'static str,
+        key_from: &'static str,
+        key: String,
+    },
+    /// Best token overlap with an alias, above [`FUZZY_THRESHOLD`].
+    Fuzzy { alias: String, score: f32 },
+}
+
+impl std::fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchRule::ExactAlias => f.write_str("exact alias"),
+            MatchRule::ReverseDnsSegment(seg) => write!(f, "reverse-DNS segment \"{seg}\""),
+            MatchRule::DesktopEntry {
+                desktop_id,
+                matched_on,
+                key_from,
+                key,
+            } => write!(
+                f,
+                "{desktop_id}.desktop ({matched_on} matched, {key_from} \"{key}\")"
+            ),
+            MatchRule::Fuzzy { alias, score } => {
+                write!(f, "fuzzy match \"{alias}\" ({score:.2})")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Resolution<'a> {
+    pub pack_id: &'a str,
+    pub rule: MatchRule,
+    pub files: &'a [PackFile],
+}
 
 #[derive(Clone, Debug)]
 pub struct ShortcutResolver {
-    shortcuts_dir: PathBuf,
-    index: HashMap<String, PathBuf>,
+    roots: Vec<ShortcutRoot>,
+    // app-native keybinding configs, merged over the pack of their app_id
+    importers: Vec<Arc<dyn Importer>>,
+    // normalized alias -> pack id
+    index: HashMap<String, String>,
+    // pack id -> files, lowest precedence first
+    packs: HashMap<String, Vec<PackFile>>,
+    // parsed once per rebuild; Err holds the read/parse error
+    parsed: HashMap<PathBuf, Result<ShortcutPack, String>>,
+    // overlays made by importers, by source file
+    imported_overlays: HashMap<PathBuf, Overlay>,
+    diagnostics: HashMap<PathBuf, Vec<Diagnostic>>,
+    // (layer, normalized alias) -> first file claiming it, for collision reports
+    claims: HashMap<(Layer, String), PathBuf>,
+    // where desktop files are read from, most important first
+    desktop_dirs: Vec<PathBuf>,
+    // installed desktop files, and normalized ID / StartupWMClass / X-Flatpak
+    // -> (index into `desktop`, which of those fields it was)
+    desktop: Vec<DesktopEntry>,
+    desktop_ids: HashMap<String, Vec<(usize, &'static str)>>,
+    // normalized pack `name` -> pack id, for desktop `Name=` lookups
+    names: HashMap<String, String>,
 }
 
 impl ShortcutResolver {
-    pub fn new(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+    /// `importers` put each app's own keybinding config over its pack, e.g.
+    /// the COSMIC settings over the desktop pack.
+    pub fn new(
+        roots: Vec<ShortcutRoot>,
+        importers: Vec<Arc<dyn Importer>>,
+    ) -> anyhow::Result<Self> {
+        Self::with_desktop_dirs(roots, importers, desktop_entries::application_dirs())
+    }
+
+    /// Like [`Self::new`], reading desktop files from `desktop_dirs` instead
+    /// of the installed application dirs.
+    pub fn with_desktop_dirs(
+        roots: Vec<ShortcutRoot>,
+        importers: Vec<Arc<dyn Importer>>,
+        desktop_dirs: Vec<PathBuf>,
+    ) -> anyhow::Result<Self> {
         let mut s = Self {
-            shortcuts_dir: dir.into(),
+            roots,
+            importers,
             index: HashMap::new(),
+            packs: HashMap::new(),
+            parsed: HashMap::new(),
+            imported_overlays: HashMap::new(),
+            diagnostics: HashMap::new(),
+            claims: HashMap::new(),
+            desktop_dirs,
+            desktop: Vec::new(),
+            desktop_ids: HashMap::new(),
+            names: HashMap::new(),
         };
+        s.rescan_desktop();
         s.rebuild_index()?;
         Ok(s)
     }
 
+    pub fn roots(&self) -> &[ShortcutRoot] {
+        &self.roots
+    }
+
+    /// What the shortcut watcher should observe: the roots, the desktop
+    /// files and the config files the importers read, existing or not.
+    pub fn watch_set(&self) -> WatchSet {
+        WatchSet {
+            pack_dirs: self.roots.iter().map(|r| r.dir.clone()).collect(),
+            desktop_dirs: self.desktop_dirs.clone(),
+            files: self.importers.iter().flat_map(|i| i.files()).collect(),
+        }
+    }
+
+    /// Re-read the desktop files, e.g. after an app was installed. They are
+    /// not reread by [`Self::rebuild_index`], as there are many of them.
+    pub fn rescan_desktop(&mut self) {
+        self.desktop = desktop_entries::scan(&self.desktop_dirs);
+        self.desktop_ids.clear();
+        for (i, e) in self.desktop.iter().enumerate() {
+            let ids = [
+                ("ID", Some(&e.id)),
+                ("StartupWMClass", e.startup_wm_class.as_ref()),
+                ("X-Flatpak", e.flatpak_id.as_ref()),
+            ];
+            for (field, value) in ids {
+                if let Some(v) = value {
+                    self.desktop_ids
+                        .entry(normalize(v))
+                        .or_default()
+                        .push((i, field));
+                }
+            }
+        }
+    }
+
     pub fn rebuild_index(&mut self) -> anyhow::Result<()> {
         self.index.clear();
+        self.packs.clear();
+        self.parsed.clear();
+        self.imported_overlays.clear();
+        self.diagnostics.clear();
+        self.claims.clear();
+        self.names.clear();
+
+        // Overlays attach after every pack is known, so a user overlay can
+        // target a pack that only exists in the system layer.
+        let mut overlays = Vec::new();
 
-        for entry in fs::read_dir(&self.shortcuts_dir)? {
-            let path = entry?.path();
-            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+        for root in self.roots.clone() {
+            let mut paths: Vec<PathBuf> = if root.dir.is_file() {
+                vec![root.dir.clone()]
+            } else if let Ok(entries) = fs::read_dir(&root.dir) {
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json"))
+                    .collect()
+            } else {
                 continue;
+            };
+            paths.sort();
+
+            for path in paths {
+                if shortcut_overlay::overlay_target(&path).is_some() {
+                    overlays.push((root.layer, path));
+                } else {
+                    self.add_file(root.layer, path);
+                }
             }
+        }
+
+        for importer in self.importers.clone() {
+            self.add_imported(importer.app_id(), importer.import());
+        }
+
+        for (layer, path) in overlays {
+            self.add_overlay(layer, path);
+        }
+
+        Ok(())
+    }
+
+    /// Put what an importer read from `app_id`'s own config under its pack.
+    /// A generated base replaces the hand-written base files; files that
+    /// extend the pack still apply on top, and generated overlays go after
+    /// them (before `.user.json` overlays).
+    fn add_imported(&mut self, app_id: &str, imported: Vec<Imported>) {
+        if imported.is_empty() {
+            return;
+        }
+
+        // looked up like any alias, so "Code" finds the pack of code.json
+        let id = normalize(app_id);
+        let pack_id = self.index.get(&id).cloned().unwrap_or_else(|| {
+            self.index.insert(id.clone(), id.clone());
+            id
+        });
 
-            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-            self.index.insert(normalize(stem), path.clone());
+        let mut packs = Vec::new();
+        let mut overlays = Vec::new();
+        for imp in imported {
+            self.diagnostics.insert(imp.path.clone(), imp.diagnostics);
+            let file = PackFile {
+                layer: imp.layer,
+                path: imp.path.clone(),
+                overlay: false,
+            };
 
-            if let Ok(bytes) = fs::read(&path) {
-                if let Ok(meta) = serde_json::from_slice::<Meta>(&bytes) {
-                    if let Some(id) = meta.app_id {
-                        self.index.insert(normalize(&id), path.clone());
-                    }
-                    for alias in meta.app_ids.unwrap_or_default() {
-                        self.index.insert(normalize(&alias), path.clone());
-                    }
+            match imp.content {
+                Some(ImportedContent::Pack(pack)) => {
+                    packs.push((pack.extends, file));
+                    self.parsed.insert(imp.path, Ok(pack));
                 }
+                Some(ImportedContent::Overlay(overlay)) => {
+                    overlays.push(PackFile {
+                        overlay: true,
+                        ..file
+                    });
+                    self.imported_overlays.insert(imp.path, overlay);
+                }
+                None => {}
             }
         }
 
-        Ok(())
+        let parsed = &self.parsed;
+        let extends = |f: &PackFile| matches!(parsed.get(&f.path), Some(Ok(p)) if p.extends);
+        let files = self.packs.entry(pack_id).or_default();
+
+        if packs.iter().any(|(extends, _)| !extends) {
+            files.retain(extends);
+        }
+        let at = files.iter().rposition(|f| !extends(f)).map_or(0, |i| i + 1);
+        files.splice(at..at, packs.into_iter().map(|(_, f)| f));
+        files.extend(overlays);
+    }
+
+    fn add_overlay(&mut self, layer: Layer, path: PathBuf) {
+        let Some(target) = shortcut_overlay::overlay_target(&path).map(normalize) else {
+            return;
+        };
+
+        let mut diags = match fs::read_to_string(&path) {
+            Ok(raw) => shortcut_overlay::validate_overlay(&path, &raw),
+            Err(e) => vec![file_diag(&path, Severity::Error, e.to_string())],
+        };
+
+        let Some(pack_id) = self.index.get(&target).cloned() else {
+            diags.push(file_diag(
+                &path,
+                Severity::Warning,
+                format!("overlay targets \"{target}\" but no such pack exists"),
+            ));
+            self.diagnostics.insert(path, diags);
+            return;
+        };
+        self.diagnostics.insert(path.clone(), diags);
+
+        self.packs.entry(pack_id).or_default().push(PackFile {
+            layer,
+            path,
+            overlay: true,
+        });
+    }
+
+    fn add_file(&mut self, layer: Layer, path: PathBuf) {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+
+        let pack = match fs::read_to_string(&path) {
+            Ok(raw) => {
+                let (pack, diags) = shortcut_pack::validate_pack(&path, &raw);
+                let first_error = diags.first().map(|d| d.to_string());
+                self.diagnostics.insert(path.clone(), diags);
+                pack.ok_or_else(|| first_error.unwrap_or_default())
+            }
+            Err(e) => Err(format!("{}: {e}", path.display())),
+        };
+
+        let mut keys = Vec::new();
+        if let Ok(p) = &pack {
+            keys.extend(p.app_id.as_deref().map(normalize));
+        }
+        keys.push(normalize(stem));
+        if let Ok(p) = &pack {
+            keys.extend(p.aliases.iter().map(|a| normalize(a)));
+        }
+        let mut seen = std::collections::HashSet::new();
+        keys.retain(|k| seen.insert(k.clone()));
+
+        // Two files in the same layer claiming one alias: the first keeps
+        // it, and this file is left with the keys nobody else claimed.
+        let mut own = Vec::new();
+        for k in keys {
+            match self.claims.get(&(layer, k.clone())) {
+                Some(prev) => {
+                    let msg = format!(
+                        "app_id \"{k}\" is already claimed by {}, which keeps it",
+                        prev.display()
+                    );
+                    self.diagnostics
+                        .entry(path.clone())
+                        .or_default()
+                        .push(file_diag(&path, Severity::Error, msg));
+                }
+                None => {
+                    self.claims.insert((layer, k.clone()), path.clone());
+                    own.push(k);
+                }
+            }
+        }
+
+        // A file whose id or stem is known from a lower layer joins that
+        // pack, so ~/.local/share/.../code.json stacks on the system one.
+        // One with every key taken still gets a pack of its own.
+        let pack_id = own
+            .iter()
+            .find_map(|k| self.index.get(k).cloned())
+            .or_else(|| own.first().cloned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        for k in own {
+            self.index.insert(k, pack_id.clone());
+        }
+        if let Some(name) = pack.as_ref().ok().and_then(|p| p.name.as_deref()) {
+            self.names
+                .entry(normalize(name))
+                .or_insert_with(|| pack_id.clone());
+        }
+        self.parsed.insert(path.clone(), pack);
+        self.packs.entry(pack_id).or_default().push(PackFile {
+            layer,
+            path,
+            overlay: false,
+        });
+    }
+
+    /// The parsed pack at `path`, as read during the last index rebuild.
+    pub fn pack(&self, path: &Path) -> anyhow::Result<&ShortcutPack> {
+        match self.parsed.get(path) {
+            Some(Ok(pack)) => Ok(pack),
+            Some(Err(e)) => Err(anyhow::anyhow!("{e}")),
+            None => Err(anyhow::anyhow!("{}: not indexed", path.display())),
+        }
+    }
+
+    /// Every indexed pack id with its files, sorted by id.
+    pub fn packs(&self) -> Vec<(&str, &[PackFile])> {
+        let mut out: Vec<_> = self
+            .packs
+            .iter()
+            .map(|(id, files)| (id.as_str(), files.as_slice()))
+            .collect();
+        out.sort_by_key(|(id, _)| *id);
+        out
+    }
+
+    /// Validation findings for every indexed file, by path then line.
+    pub fn all_diagnostics(&self) -> Vec<&Diagnostic> {
+        let mut out: Vec<&Diagnostic> = self.diagnostics.values().flatten().collect();
+        out.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+        out
+    }
+
+    /// Validation findings for the given files.
+    pub fn diagnostics<'a>(
+        &'a self,
+        files: &'a [PackFile],
+    ) -> impl Iterator<Item = &'a Diagnostic> + 'a {
+        files
+            .iter()
+            .filter_map(|f| self.diagnostics.get(&f.path))
+            .flatten()
+    }
+
+    /// Files backing `app_id`, lowest precedence first.
+    pub fn resolve(&self, app_id: &str) -> Option<&[PackFile]> {
+        self.resolve_explained(app_id).map(|r| r.files)
     }
 
-    pub fn resolve(&self, app_id: &str) -> Option<PathBuf> {
+    /// Resolve `app_id` and report which stage matched. Stages run in order:
+    /// exact alias, reverse-DNS last segment, installed desktop files (ID,
+    /// `StartupWMClass`, Flatpak ID -> ID, `Exec`, `Name`), then a scored fuzzy match.
+    pub fn resolve_explained(&self, app_id: &str) -> Option<Resolution<'_>> {
         let q = normalize(app_id);
+        if q.is_empty() {
+            return None;
+        }
 
-        if let Some(p) = self.index.get(&q) {
-            return Some(p.clone());
+        if let Some(r) = self.lookup(&q, MatchRule::ExactAlias) {
+            return Some(r);
         }
 
-        let mut keys: Vec<_> = self.index.keys().collect();
-        keys.sort();
+        if let Some(seg) = last_dns_segment(&q) {
+            if let Some(r) = self.lookup(seg, MatchRule::ReverseDnsSegment(seg.to_string())) {
+                return Some(r);
+            }
+        }
 
-        for k in keys {
-            if k.contains(&q) || q.contains(k) {
-                return self.index.get(k).cloned();
+        if let Some(r) = self.desktop_lookup(&q) {
+            return Some(r);
+        }
+
+        self.fuzzy(&q)
+    }
+
+    fn lookup(&self, key: &str, rule: MatchRule) -> Option<Resolution<'_>> {
+        self.index
+            .get(key)
+            .and_then(|id| self.pack_resolution(id, rule))
+    }
+
+    fn pack_resolution(&self, pack_id: &str, rule: MatchRule) -> Option<Resolution<'_>> {
+        let (pack_id, files) = self.packs.get_key_value(pack_id)?;
+        Some(Resolution {
+            pack_id,
+            rule,
+            files,
+        })
+    }
+
+    /// Desktop files identifying `q`; try each of their fields as a pack key,
+    /// so a pack keyed on "firefox" matches the `org.mozilla.firefox` Flatpak.
+    fn desktop_lookup(&self, q: &str) -> Option<Resolution<'_>> {
+        for &(i, matched_on) in self.desktop_ids.get(q)? {
+            let e = &self.desktop[i];
+            let fields = [
+                ("ID", Some(&e.id)),
+                ("X-Flatpak", e.flatpak_id.as_ref()),
+                ("StartupWMClass", e.startup_wm_class.as_ref()),
+                ("Exec", e.exec_binary.as_ref()),
+                ("Name", e.name.as_ref()),
+            ];
+
+            for (key_from, value) in fields {
+                let Some(value) = value else { continue };
+                let value = normalize(value);
+
+                let rule = |key: &str| MatchRule::DesktopEntry {
+                    desktop_id: e.id.clone(),
+                    matched_on,
+                    key_from,
+                    key: key.to_string(),
+                };
+
+                let found = self
+                    .lookup(&value, rule(&value))
+                    .or_else(|| {
+                        last_dns_segment(&value).and_then(|seg| self.lookup(seg, rule(seg)))
+                    })
+                    .or_else(|| {
+                        self.names
+                            .get(&value)
+                            .and_then(|id| self.pack_resolution(id, rule(&value)))
+                    });
+                if found.is_some() {
+                    return found;
+                }
             }
         }
 
         None
     }
+
+    /// Highest-scoring alias; ties go to the longer, then lexically first alias.
+    fn fuzzy(&self, q: &str) -> Option<Resolution<'_>> {
+        let q_tokens = tokens(q);
+        if q_tokens.is_empty() {
+            return None;
+        }
+
+        let mut best: Option<(f32, &String)> = None;
+        for alias in self.index.keys() {
+            let score = token_score(&q_tokens, &tokens(alias));
+            if score <= FUZZY_THRESHOLD {
+                continue;
+            }
+
+            let better = match best {
+                None => true,
+                Some((s, a)) => score
+                    .total_cmp(&s)
+                    .then(alias.len().cmp(&a.len()))
+                    .then(a.cmp(alias))
+                    .is_gt(),
+            };
+            if better {
+                best = Some((score, alias));
+            }
+        }
+
+        let (score, alias) = best?;
+        self.lookup(
+            alias,
+            MatchRule::Fuzzy {
+                alias: alias.clone(),
+                score,
+            },
+        )
+    }
+
+    /// Load and merge every layer for `app_id`. A higher file replaces what is
+    /// below it, unless it sets `"extends": true`, in which case its entries are
+    /// added and ones with the same `keys` override the lower entry. Overlays
+    /// (`*.user.json`) are applied last.
+    pub fn load(&self, app_id: &str) -> anyhow::Result<Option<LoadedPack>> {
+        match self.resolve(app_id) {
+            Some(files) => self.merge(files).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Merge a stack of files as returned by [`Self::resolve`].
+    pub fn merge(&self, files: &[PackFile]) -> anyhow::Result<LoadedPack> {
+        self.merge_files(files, true)
+    }
+
+    /// Contexts for the [`TITLE_HINT_PREFIX`] words in `title`, each nesting
+    /// the pack it names. Hints naming no pack are ignored.
+    pub fn hinted_contexts(&self, title: &str) -> Vec<LoadedContext> {
+        title
+            .split_whitespace()
+            .filter_map(|word| {
+                let app_id = word.strip_prefix(TITLE_HINT_PREFIX)?;
+                let files = self.resolve(app_id)?;
+                let name = self.pack(&files.first()?.path).ok()?.name.clone();
+                let name = name.unwrap_or_else(|| app_id.to_string());
+                Some(LoadedContext {
+                    entries: self.nested_entries(&name, app_id).ok()?,
+                    name,
+                    title: Regex::new(&regex::escape(word)).ok()?,
+                    replace: false,
+                    pack: Some(app_id.to_string()),
+                    hinted: true,
+                })
+            })
+            .collect()
+    }
+
+    /// The merged entries of `app_id`'s pack, filed under `ctx`: its
+    /// categories become "tmux: Windows", uncategorized ones just "tmux".
+    fn nested_entries(&self, ctx: &str, app_id: &str) -> anyhow::Result<Vec<LoadedEntry>> {
+        // unresolved packs are reported by the linter
+        let Some(files) = self.resolve(app_id) else {
+            return Ok(Vec::new());
+        };
+        let pack = self.merge_files(files, false)?;
+
+        Ok(pack
+            .entries
+            .into_iter()
+            .map(|mut e| {
+                e.category = Some(match e.category {
+                    Some(c) => format!("{ctx}: {c}"),
+                    None => ctx.to_string(),
+                });
+                e
+            })
+            .collect())
+    }
+
+    /// `nest` loads the packs of `pack` contexts; nested packs don't nest
+    /// further, so packs nesting each other can't recurse.
+    fn merge_files(&self, files: &[PackFile], nest: bool) -> anyhow::Result<LoadedPack> {
+        let mut name = None;
+        let mut categories = Vec::new();
+        let mut entries: Vec<LoadedEntry> = Vec::new();
+        let mut contexts: Vec<LoadedContext> = Vec::new();
+
+        for file in files {
+            if file.overlay {
+                let overlay = match self.imported_overlays.get(&file.path) {
+                    Some(o) => o.clone(),
+                    // already reported by validate_overlay; the pack stays usable
+                    None => match Overlay::read(&file.path) {
+                        Ok(o) => o,
+                        Err(_) => continue,
+                    },
+                };
+                overlay.apply(&mut entries, file.layer);
+                for ctx in &mut contexts {
+                    overlay.apply_edits(&mut ctx.entries, file.layer);
+                }
+                continue;
+            }
+
+            let pack = self.pack(&file.path)?;
+
+            if !pack.extends {
+                entries.clear();
+                contexts.clear();
+            }
+            if pack.name.is_some() {
+                name = pack.name.clone();
+            }
+            if !pack.categories.is_empty() {
+                categories = pack.categories.clone();
+            }
+
+            for s in &pack.shortcuts {
+                let entry = LoadedEntry::from_shortcut(s, file.layer);
+
+                match entries
+                    .iter_mut()
+                    .find(|e| e.keys == entry.keys && e.layout == entry.layout)
+                {
+                    Some(existing) if pack.extends => *existing = entry,
+                    _ => entries.push(entry),
+                }
+            }
+
+            // A context with the same name as one below replaces it.
+            for c in &pack.contexts {
+                // Bad patterns are reported by the validator.
+                let Ok(title) = Regex::new(&c.title_matches) else {
+                    continue;
+                };
+                let ctx = LoadedContext {
+                    name: c.name.clone(),
+                    title,
+                    replace: c.replace,
+                    pack: c.pack.clone(),
+                    hinted: false,
+                    entries: c
+                        .shortcuts
+                        .iter()
+                        .map(|s| LoadedEntry::from_shortcut(s, file.layer))
+                        .collect(),
+                };
+
+                match contexts.iter_mut().find(|x| x.name == ctx.name) {
+                    Some(existing) => *existing = ctx,
+                    None => contexts.push(ctx),
+                }
+            }
+        }
+
+        if nest {
+            for ctx in &mut contexts {
+                let Some(app_id) = &ctx.pack else { continue };
+                // the context's own shortcuts come last, so theirs win
+                let mut nested = self.nested_entries(&ctx.name, app_id)?;
+                nested.append(&mut ctx.entries);
+                ctx.entries = nested;
+            }
+        }
+
+        Ok(LoadedPack {
+            name,
+            categories,
+            files: files.to_vec(),
+            entries,
+            contexts,
+        })
+    }
+}
+
+fn file_diag(path: &Path, severity: Severity, message: String) -> Diagnostic {
+    Diagnostic {
+        path: path.to_path_buf(),
+        line: None,
+        severity,
+        message,
+    }
 }
 
-#[derive(Deserialize)]
-struct Meta {
-    app_id: Option<String>,
-    app_ids: Option<Vec<String>>,
+/// "org.mozilla.firefox" -> "firefox"; `None` for ids without dots.
+fn last_dns_segment(id: &str) -> Option<&str> {
+    id.rsplit_once('.')
+        .map(|(_, seg)| seg)
+        .filter(|seg| !seg.is_empty())
+}
+
+fn tokens(s: &str) -> Vec<&str> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty() && !GENERIC_TOKENS.contains(t))
+        .collect()
+}
+
+/// Share of tokens the two ids have in common, relative to the longer one.
+/// Tokens of 5+ chars also match at edit distance 1 to absorb typos.
+fn token_score(a: &[&str], b: &[&str]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let matched = a
+        .iter()
+        .filter(|ta| {
+            b.iter().any(|tb| {
+                ta == &tb || (ta.len() >= 5 && tb.len() >= 5 && levenshtein(ta, tb) <= 1)
+            })
+        })
+        .count();
+
+    matched as f32 / a.len().max(b.len()) as f32
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+
+    prev[b.len()]
 }
 
 fn normalize(s: &str) -> String {
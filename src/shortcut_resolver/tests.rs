@@ -0,0 +1,457 @@
+use std::path::{Path, PathBuf};
+
+use super::{Layer, LoadedPack, MatchRule, ShortcutResolver, ShortcutRoot};
+use crate::shortcut_pack::Severity;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/shortcut_resolver/fixtures")
+        .join(name)
+}
+
+/// A resolver over the fixture packs in `dir`, seeing only the fixture
+/// desktop files.
+fn resolver(dir: &str) -> ShortcutResolver {
+    let root = ShortcutRoot {
+        layer: Layer::System,
+        dir: fixture(dir),
+    };
+    let desktop_dirs = vec![fixture("applications")];
+    ShortcutResolver::with_desktop_dirs(vec![root], Vec::new(), desktop_dirs).unwrap()
+}
+
+/// The pack `app_id` resolves to, and by which rule.
+fn matched(r: &ShortcutResolver, app_id: &str) -> Option<(String, MatchRule)> {
+    r.resolve_explained(app_id)
+        .map(|m| (m.pack_id.to_string(), m.rule))
+}
+
+/// A resolver with one root per layer under a fresh directory, holding
+/// `files` as (layer, file name, contents).
+fn stacked(name: &str, files: &[(Layer, &str, &str)]) -> ShortcutResolver {
+    let dir = std::env::temp_dir().join(format!("orbitkeys-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let roots: Vec<_> = [Layer::System, Layer::User, Layer::Project]
+        .into_iter()
+        .map(|layer| ShortcutRoot {
+            layer,
+            dir: dir.join(layer.label()),
+        })
+        .collect();
+    for root in &roots {
+        std::fs::create_dir_all(&root.dir).unwrap();
+    }
+    for (layer, file, raw) in files {
+        std::fs::write(dir.join(layer.label()).join(file), raw).unwrap();
+    }
+
+    ShortcutResolver::with_desktop_dirs(roots, Vec::new(), Vec::new()).unwrap()
+}
+
+/// The merged entries of `app_id` as "keys: desc (layer)".
+fn shown(r: &ShortcutResolver, app_id: &str) -> Vec<String> {
+    let pack = r.load(app_id).unwrap().expect("no pack");
+    pack.entries
+        .iter()
+        .map(|e| format!("{}: {} ({})", e.keys, e.desc, e.layer.label()))
+        .collect()
+}
+
+fn resolved<'a>(r: &'a ShortcutResolver, app_id: &str) -> Vec<&'a Path> {
+    r.resolve(app_id)
+        .unwrap_or_default()
+        .iter()
+        .map(|f| f.path.as_path())
+        .collect()
+}
+
+#[test]
+fn same_layer_collision_keeps_the_first_claim() {
+    let r = resolver("collision");
+    let first = fixture("collision/first.json");
+    let second = fixture("collision/second.json");
+    let shared = fixture("collision/shared.json");
+
+    assert_eq!(resolved(&r, "editor"), [first.as_path()]);
+    assert_eq!(resolved(&r, "shared"), [first.as_path()]);
+    // not merged into the pack it collided with
+    assert_eq!(resolved(&r, "other-editor"), [second.as_path()]);
+
+    // every key taken, yet still a pack of its own
+    let alone = r
+        .packs()
+        .into_iter()
+        .find(|(_, files)| files[0].path == shared);
+    assert_eq!(alone.map(|(_, files)| files.len()), Some(1));
+
+    let errors: Vec<_> = r
+        .all_diagnostics()
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 4, "{errors:#?}");
+    assert!(errors
+        .iter()
+        .all(|d| d.path != first && d.message.contains("already claimed by")));
+}
+
+#[test]
+fn exact_alias() {
+    let r = resolver("stages");
+    assert_eq!(
+        matched(&r, " Firefox "),
+        Some(("firefox".into(), MatchRule::ExactAlias))
+    );
+}
+
+#[test]
+fn reverse_dns_segment() {
+    let r = resolver("stages");
+    assert_eq!(
+        matched(&r, "org.mozilla.firefox"),
+        Some((
+            "firefox".into(),
+            MatchRule::ReverseDnsSegment("firefox".into())
+        ))
+    );
+}
+
+#[test]
+fn desktop_entry() {
+    let r = resolver("stages");
+    let rule = MatchRule::DesktopEntry {
+        desktop_id: "org.example.Writer".into(),
+        matched_on: "ID",
+        key_from: "Exec",
+        key: "scribe".into(),
+    };
+    assert_eq!(
+        matched(&r, "org.example.Writer"),
+        Some(("scribe".into(), rule))
+    );
+}
+
+#[test]
+fn fuzzy_accepts_most_tokens_shared() {
+    let r = resolver("stages");
+
+    let Some((pack, MatchRule::Fuzzy { alias, score })) = matched(&r, "cosmic-terminal-nightly")
+    else {
+        panic!("no fuzzy match");
+    };
+    assert_eq!(
+        (pack.as_str(), alias.as_str()),
+        ("cosmic-terminal", "cosmic-terminal")
+    );
+    assert!(score > 0.6);
+
+    // a typo in a long token still counts
+    let typo = matched(&r, "cosmic-termnal").map(|(pack, _)| pack);
+    assert_eq!(typo.as_deref(), Some("cosmic-terminal"));
+}
+
+#[test]
+fn fuzzy_rejects_half_the_tokens_shared() {
+    let r = resolver("stages");
+    assert_eq!(matched(&r, "gnome-terminal"), None);
+    assert_eq!(matched(&r, "libreoffice-base"), None);
+}
+
+const SYSTEM_EDITOR: &str = r#"{
+  "app_id": "editor",
+  "shortcuts": [
+    { "keys": "Ctrl+S", "desc": "Save" },
+    { "keys": "Ctrl+O", "desc": "Open" }
+  ],
+  "contexts": [
+    { "name": "diff", "title_matches": "diff",
+      "shortcuts": [{ "keys": "F7", "desc": "Next change" }] }
+  ]
+}"#;
+
+#[test]
+fn layers_stack_system_then_user_then_project() {
+    let user = r#"{ "app_id": "editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+S", "desc": "Save all" },
+        { "keys": "Ctrl+W", "desc": "Close" }
+    ] }"#;
+    let project = r#"{ "app_id": "editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+W", "desc": "Close tab" }
+    ] }"#;
+    // listed out of order: the layer decides, not the order files are found
+    let r = stacked(
+        "stacking",
+        &[
+            (Layer::Project, "editor.json", project),
+            (Layer::System, "editor.json", SYSTEM_EDITOR),
+            (Layer::User, "editor.json", user),
+        ],
+    );
+
+    let layers: Vec<_> = r
+        .resolve("editor")
+        .unwrap()
+        .iter()
+        .map(|f| f.layer)
+        .collect();
+    assert_eq!(layers, [Layer::System, Layer::User, Layer::Project]);
+
+    assert_eq!(
+        shown(&r, "editor"),
+        [
+            "Ctrl+S: Save all (user)",
+            "Ctrl+O: Open (system)",
+            "Ctrl+W: Close tab (project)",
+        ]
+    );
+    let pack = r.load("editor").unwrap().unwrap();
+    assert_eq!(pack.contexts.len(), 1);
+    assert_eq!(pack.contexts[0].entries[0].layer, Layer::System);
+}
+
+#[test]
+fn a_file_without_extends_replaces_the_layers_below() {
+    let user = r#"{ "app_id": "editor", "shortcuts": [
+        { "keys": "Ctrl+Q", "desc": "Quit" }
+    ] }"#;
+    let project = r#"{ "app_id": "editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+S", "desc": "Save" }
+    ] }"#;
+    let r = stacked(
+        "replacing",
+        &[
+            (Layer::System, "editor.json", SYSTEM_EDITOR),
+            (Layer::User, "editor.json", user),
+            (Layer::Project, "editor.json", project),
+        ],
+    );
+
+    // the system entries and its context are gone; the project still extends
+    assert_eq!(
+        shown(&r, "editor"),
+        ["Ctrl+Q: Quit (user)", "Ctrl+S: Save (project)"]
+    );
+    assert!(r.load("editor").unwrap().unwrap().contexts.is_empty());
+}
+
+#[test]
+fn files_join_a_lower_pack_by_app_id() {
+    // named differently, but its app_id is the system pack's
+    let user = r#"{ "app_id": "Editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+W", "desc": "Close" }
+    ] }"#;
+    let r = stacked(
+        "joining",
+        &[
+            (Layer::System, "editor.json", SYSTEM_EDITOR),
+            (Layer::User, "my-editor.json", user),
+        ],
+    );
+
+    assert_eq!(
+        shown(&r, "my-editor"),
+        [
+            "Ctrl+S: Save (system)",
+            "Ctrl+O: Open (system)",
+            "Ctrl+W: Close (user)",
+        ]
+    );
+}
+
+#[test]
+fn overlays_apply_after_every_layer() {
+    let user = r#"{ "app_id": "editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+W", "desc": "Close" }
+    ] }"#;
+    // in the system layer, yet it sees the user file's entry
+    let overlay = r#"{ "rebind": [{ "desc": "Close", "to": "Ctrl+F4" }] }"#;
+    let r = stacked(
+        "overlaid",
+        &[
+            (Layer::System, "editor.json", SYSTEM_EDITOR),
+            (Layer::System, "editor.user.json", overlay),
+            (Layer::User, "editor.json", user),
+            (Layer::Project, "editor.user.json", "{ \"hide\": "),
+        ],
+    );
+
+    let files: Vec<_> = r
+        .resolve("editor")
+        .unwrap()
+        .iter()
+        .map(|f| (f.layer, f.overlay))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            (Layer::System, false),
+            (Layer::User, false),
+            (Layer::System, true),
+            (Layer::Project, true),
+        ]
+    );
+
+    // the broken project overlay is reported and skipped
+    assert_eq!(
+        shown(&r, "editor"),
+        [
+            "Ctrl+S: Save (system)",
+            "Ctrl+O: Open (system)",
+            "Ctrl+F4: Close (system)",
+        ]
+    );
+    let errors: Vec<_> = r
+        .all_diagnostics()
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 1, "{errors:#?}");
+    assert!(errors[0].path.ends_with("project/editor.user.json"));
+}
+
+#[test]
+fn same_layer_claims_keep_the_first_file_over_a_lower_pack() {
+    let b = r#"{ "app_id": "editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+B", "desc": "From b" }
+    ] }"#;
+    let a = r#"{ "app_id": "editor", "extends": true, "shortcuts": [
+        { "keys": "Ctrl+A", "desc": "From a" }
+    ] }"#;
+    let r = stacked(
+        "claims",
+        &[
+            (Layer::System, "editor.json", SYSTEM_EDITOR),
+            (Layer::User, "b.json", b),
+            (Layer::User, "a.json", a),
+        ],
+    );
+
+    // a.json sorts first, so it joins the system pack; b.json stands alone
+    assert_eq!(
+        shown(&r, "editor"),
+        [
+            "Ctrl+S: Save (system)",
+            "Ctrl+O: Open (system)",
+            "Ctrl+A: From a (user)",
+        ]
+    );
+    assert_eq!(shown(&r, "b"), ["Ctrl+B: From b (user)"]);
+
+    let errors: Vec<_> = r
+        .all_diagnostics()
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 1, "{errors:#?}");
+    assert!(errors[0].path.ends_with("user/b.json"));
+    assert!(errors[0]
+        .message
+        .starts_with("app_id \"editor\" is already claimed by"));
+}
+
+/// What `pack` shows for a window titled `title`, as "keys: desc (category)".
+fn titled(pack: &LoadedPack, title: &str) -> Vec<String> {
+    pack.entries_for_title(title)
+        .iter()
+        .map(|e| {
+            format!(
+                "{}: {} ({})",
+                e.keys,
+                e.desc,
+                e.category.as_deref().unwrap_or("")
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn title_contexts_add_to_the_pack() {
+    let r = resolver("contexts");
+    let firefox = r.load("firefox").unwrap().unwrap();
+
+    let docs = "Budget - Google Docs — Mozilla Firefox";
+    let active: Vec<_> = firefox
+        .active_contexts(docs)
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(active, ["Google Docs"]);
+    // the context's binding takes the place of the pack's
+    assert_eq!(
+        titled(&firefox, docs),
+        [
+            "Ctrl+T: New tab (Tabs)",
+            "Ctrl+Shift+C: Word count (Google Docs)",
+            "Ctrl+/: Show keyboard shortcuts (Google Docs)",
+        ]
+    );
+
+    assert!(firefox.active_contexts("Mozilla Firefox").is_empty());
+    assert_eq!(
+        titled(&firefox, "Mozilla Firefox"),
+        [
+            "Ctrl+T: New tab (Tabs)",
+            "Ctrl+Shift+C: Inspect element (Developer)",
+        ]
+    );
+}
+
+#[test]
+fn replacing_contexts_stand_alone() {
+    let r = resolver("contexts");
+    let terminal = r.load("terminal").unwrap().unwrap();
+    assert_eq!(titled(&terminal, "less notes.txt"), ["Q: Quit ()"]);
+}
+
+#[test]
+fn pack_contexts_nest_under_their_name() {
+    let r = resolver("contexts");
+    let terminal = r.load("terminal").unwrap().unwrap();
+
+    let tmux = &terminal.contexts[0];
+    assert_eq!(tmux.pack.as_deref(), Some("tmux"));
+    assert!(!tmux.hinted);
+    // nested first, so the context's own Ctrl+B D wins; tmux's context
+    // nesting the terminal again isn't followed
+    assert_eq!(
+        titled(&terminal, "tmux new -s work"),
+        [
+            "Ctrl+Shift+T: New tab (Tabs)",
+            "Ctrl+Shift+C: Copy (Editing)",
+            "Ctrl+B D: Detach and keep running (tmux: Sessions)",
+            "Ctrl+B C: New window (tmux: Windows)",
+            "Ctrl+B ?: List keys (tmux)",
+        ]
+    );
+}
+
+#[test]
+fn title_hints_nest_the_named_pack() {
+    let r = resolver("contexts");
+    let mut terminal = r.load("terminal").unwrap().unwrap();
+
+    let title = "~/src orbitkeys:tmux orbitkeys:nope";
+    let hinted = r.hinted_contexts(title);
+    assert_eq!(hinted.len(), 1, "unknown packs are ignored");
+    assert!(hinted[0].hinted);
+    assert_eq!(hinted[0].name, "tmux");
+    assert!(hinted[0].title.is_match(title));
+
+    terminal.set_hinted(hinted);
+    assert_eq!(
+        titled(&terminal, title),
+        [
+            "Ctrl+Shift+T: New tab (Tabs)",
+            "Ctrl+Shift+C: Copy (Editing)",
+            "Ctrl+B D: Detach (tmux: Sessions)",
+            "Ctrl+B C: New window (tmux: Windows)",
+            "Ctrl+B ?: List keys (tmux)",
+        ]
+    );
+
+    // a new title drops the old hints
+    terminal.set_hinted(r.hinted_contexts("~/src"));
+    assert_eq!(terminal.contexts.len(), 2);
+    assert!(terminal.contexts.iter().all(|c| !c.hinted));
+}
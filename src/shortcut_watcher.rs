@@ -0,0 +1,263 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::shutdown::Shutdown;
+
+#[cfg(test)]
+mod tests;
+
+/// Editors tend to write, rename and chmod a file in quick succession on save.
+/// Wait this long after the last event before reporting a batch.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Delay before watching again after a failure; doubles up to [`RETRY_MAX`]
+/// and starts over once watching works.
+const RETRY_INITIAL: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// What the shortcut watcher reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReloadEvent {
+    /// Wanted files that were created, modified, renamed or removed. After a
+    /// failure, every wanted file there is, since changes may have been missed.
+    Changed(Vec<PathBuf>),
+    /// Watching failed, e.g. inotify's watch limit was reached. The watcher
+    /// tries again after `retry_in`.
+    Failed { error: String, retry_in: Duration },
+}
+
+/// What the shortcut watcher reports changes to. Directories are watched
+/// without their subdirectories.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WatchSet {
+    /// Shortcut roots: every `.json` file in them.
+    pub pack_dirs: Vec<PathBuf>,
+    /// Application dirs: every `.desktop` file in them.
+    pub desktop_dirs: Vec<PathBuf>,
+    /// The importers' config files, and nothing else in their directories.
+    pub files: Vec<PathBuf>,
+}
+
+impl WatchSet {
+    /// Directories holding something to watch, each once.
+    fn dirs(&self) -> BTreeSet<&Path> {
+        let parents = self.files.iter().filter_map(|f| f.parent());
+        self.pack_dirs
+            .iter()
+            .chain(&self.desktop_dirs)
+            .map(PathBuf::as_path)
+            .chain(parents)
+            .collect()
+    }
+
+    /// Whether a change to `path` is reported.
+    pub fn wants(&self, path: &Path) -> bool {
+        let in_dirs = |dirs: &[PathBuf], ext: &str| {
+            path.extension().is_some_and(|e| e == ext)
+                && path.parent().is_some_and(|p| dirs.iter().any(|d| d == p))
+        };
+        in_dirs(&self.pack_dirs, "json")
+            || in_dirs(&self.desktop_dirs, "desktop")
+            || self.files.iter().any(|f| f == path)
+    }
+
+    /// Every wanted file that exists now.
+    fn existing(&self) -> Vec<PathBuf> {
+        let found: BTreeSet<PathBuf> = self
+            .dirs()
+            .into_iter()
+            .flat_map(|d| wanted_in(self, d))
+            .collect();
+        found.into_iter().collect()
+    }
+}
+
+/// Watch `set` (inotify on Linux) and call `on_event` with the files it
+/// wants that were created, modified, renamed or removed.
+/// A directory that doesn't exist yet is waited for from its nearest existing
+/// parent; once it appears, the files it already holds count as created.
+/// When watching fails it reports why and starts over after a delay.
+/// This function blocks until `shutdown` is stopped.
+pub fn run_shortcut_watcher<F>(set: WatchSet, shutdown: Shutdown, mut on_event: F)
+where
+    F: FnMut(ReloadEvent) + Send + 'static,
+{
+    let mut retry_in = RETRY_INITIAL;
+    let mut failed = false;
+
+    while !shutdown.is_stopped() {
+        let Err(e) = watch(&set, &shutdown, failed, &mut retry_in, &mut on_event) else {
+            return;
+        };
+        failed = true;
+
+        on_event(ReloadEvent::Failed {
+            error: format!("{e:#}"),
+            retry_in,
+        });
+        if shutdown.wait_timeout(retry_in) {
+            return;
+        }
+        retry_in = (retry_in * 2).min(RETRY_MAX);
+    }
+}
+
+/// One run of the watcher, until `shutdown` or an error. After a failure,
+/// reports every wanted file once watching is set up again.
+fn watch<F>(
+    set: &WatchSet,
+    shutdown: &Shutdown,
+    failed: bool,
+    retry_in: &mut Duration,
+    on_event: &mut F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(ReloadEvent),
+{
+    // `None` is the shutdown wake-up.
+    let (tx, rx) = mpsc::channel::<Option<notify::Result<notify::Event>>>();
+
+    let stop_tx = tx.clone();
+    shutdown.on_stop(move || {
+        let _ = stop_tx.send(None);
+    });
+
+    let watcher = notify::recommended_watcher(move |ev| {
+        let _ = tx.send(Some(ev));
+    })?;
+    let mut watched = Watched {
+        watcher,
+        dirs: BTreeSet::new(),
+    };
+    watched.sync(set, None)?;
+
+    *retry_in = RETRY_INITIAL;
+    if failed {
+        on_event(ReloadEvent::Changed(set.existing()));
+    }
+
+    loop {
+        // Block until something happens, then keep collecting until it goes quiet.
+        let mut touched = BTreeSet::new();
+        let Some(ev) = rx.recv()? else { return Ok(()) };
+        collect(ev, &mut touched)?;
+
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Some(ev)) => collect(ev, &mut touched)?,
+                Ok(None) => return Ok(()),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        let mut changed: BTreeSet<PathBuf> =
+            touched.iter().filter(|p| set.wants(p)).cloned().collect();
+
+        // A watched dir, or one on the way to it, came or went.
+        let moved = touched
+            .iter()
+            .any(|p| set.dirs().iter().any(|d| d.starts_with(p)));
+        if moved {
+            changed.extend(watched.sync(set, Some(&touched))?);
+        }
+
+        if !changed.is_empty() {
+            on_event(ReloadEvent::Changed(changed.into_iter().collect()));
+        }
+    }
+}
+
+/// The directories actually watched: each of the set's, or while it is
+/// missing, its nearest existing parent.
+struct Watched {
+    watcher: RecommendedWatcher,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl Watched {
+    /// Watch what `set` needs now, given the paths just `touched` (`None`
+    /// at start). Returns what the dirs that appeared since already hold.
+    fn sync(
+        &mut self,
+        set: &WatchSet,
+        touched: Option<&BTreeSet<PathBuf>>,
+    ) -> notify::Result<Vec<PathBuf>> {
+        let wanted = set.dirs();
+        let needed: BTreeSet<PathBuf> = wanted
+            .iter()
+            .filter_map(|d| d.ancestors().find(|a| a.is_dir()))
+            .map(Path::to_path_buf)
+            .collect();
+
+        for gone in self.dirs.difference(&needed) {
+            // fails if the dir itself was removed, which dropped the watch
+            let _ = self.watcher.unwatch(gone);
+        }
+
+        let mut found = Vec::new();
+        // a dir removed and created again lost its watch as well
+        let fresh = needed
+            .iter()
+            .filter(|d| !self.dirs.contains(*d) || touched.is_some_and(|t| t.contains(*d)));
+        for dir in fresh {
+            match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {}
+                // removed again since; its parent's watch will tell
+                Err(e) if vanished(&e) => continue,
+                // out of watches, or not allowed to read it
+                Err(e) => return Err(e),
+            }
+            if touched.is_some() && wanted.contains(dir.as_path()) {
+                found.extend(wanted_in(set, dir));
+            }
+        }
+
+        self.dirs = needed;
+        Ok(found)
+    }
+}
+
+/// The wanted files directly in `dir`.
+fn wanted_in(set: &WatchSet, dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| set.wants(p))
+        .collect()
+}
+
+fn vanished(e: &notify::Error) -> bool {
+    match &e.kind {
+        notify::ErrorKind::PathNotFound => true,
+        notify::ErrorKind::Io(io) => io.kind() == std::io::ErrorKind::NotFound,
+        _ => false,
+    }
+}
+
+fn collect(
+    ev: notify::Result<notify::Event>,
+    touched: &mut BTreeSet<PathBuf>,
+) -> notify::Result<()> {
+    let ev = ev?;
+
+    let relevant = matches!(
+        ev.kind,
+        EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Name(_))
+            | EventKind::Modify(ModifyKind::Data(_))
+            | EventKind::Modify(ModifyKind::Any)
+    );
+    if relevant {
+        touched.extend(ev.paths);
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use super::{run_shortcut_watcher, ReloadEvent, WatchSet};
+use crate::shutdown::Shutdown;
+
+/// A fresh directory for one test.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orbitkeys-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs the watcher on its own thread until dropped.
+struct Running {
+    shutdown: Shutdown,
+    changes: mpsc::Receiver<Vec<PathBuf>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Running {
+    fn start(set: WatchSet) -> Self {
+        let shutdown = Shutdown::new().unwrap();
+        let (tx, changes) = mpsc::channel();
+        let stop = shutdown.clone();
+        let thread = std::thread::spawn(move || {
+            run_shortcut_watcher(set, stop, move |ev| {
+                if let ReloadEvent::Changed(paths) = ev {
+                    let _ = tx.send(paths);
+                }
+            });
+        });
+        // let it set up its watches
+        std::thread::sleep(Duration::from_millis(200));
+        Self {
+            shutdown,
+            changes,
+            thread: Some(thread),
+        }
+    }
+
+    fn next(&self) -> Vec<PathBuf> {
+        self.changes.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.shutdown.stop();
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+#[test]
+fn json_counts_only_in_pack_dirs() {
+    let set = WatchSet {
+        pack_dirs: vec!["/packs".into()],
+        desktop_dirs: vec!["/apps".into()],
+        files: vec!["/vault/.obsidian/hotkeys.json".into()],
+    };
+    let wants = |p: &str| set.wants(Path::new(p));
+
+    assert!(wants("/packs/code.json"));
+    assert!(!wants("/packs/notes.txt"));
+    assert!(!wants("/packs/old/code.json"));
+    assert!(wants("/apps/firefox.desktop"));
+    assert!(!wants("/apps/firefox.json"));
+
+    assert!(wants("/vault/.obsidian/hotkeys.json"));
+    assert!(!wants("/vault/.obsidian/workspace.json"));
+}
+
+#[test]
+fn reports_only_wanted_files() {
+    let dir = scratch_dir("watch-wanted");
+    let config = dir.join("config");
+    std::fs::create_dir(&config).unwrap();
+    let set = WatchSet {
+        pack_dirs: vec![dir.clone()],
+        files: vec![config.join("keybindings.json")],
+        ..WatchSet::default()
+    };
+    let watcher = Running::start(set);
+
+    std::fs::write(config.join("settings.json"), "{}").unwrap();
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+    std::fs::write(config.join("keybindings.json"), "[]").unwrap();
+    std::fs::write(dir.join("code.json"), "{}").unwrap();
+
+    assert_eq!(
+        watcher.next(),
+        [dir.join("code.json"), config.join("keybindings.json")]
+    );
+}
+
+#[test]
+fn waits_for_dirs_created_later() {
+    let dir = scratch_dir("watch-later");
+    let config = dir.join("Code/User");
+    let set = WatchSet {
+        files: vec![config.join("keybindings.json")],
+        ..WatchSet::default()
+    };
+    let watcher = Running::start(set);
+
+    std::fs::create_dir_all(&config).unwrap();
+    std::fs::write(config.join("keybindings.json"), "[]").unwrap();
+    assert_eq!(watcher.next(), [config.join("keybindings.json")]);
+
+    // now watched directly
+    std::fs::write(config.join("keybindings.json"), "[{}]").unwrap();
+    assert_eq!(watcher.next(), [config.join("keybindings.json")]);
+}
+
+#[test]
+fn a_dotfile_ignores_the_rest_of_home() {
+    let home = scratch_dir("watch-home");
+    let set = WatchSet {
+        files: vec![home.join(".tmux.conf")],
+        ..WatchSet::default()
+    };
+    let watcher = Running::start(set);
+
+    std::fs::write(home.join(".bash_history"), "ls").unwrap();
+    std::fs::write(home.join("settings.json"), "{}").unwrap();
+    std::fs::create_dir(home.join("Downloads")).unwrap();
+    std::fs::write(home.join(".tmux.conf"), "set -g prefix C-a").unwrap();
+
+    assert_eq!(watcher.next(), [home.join(".tmux.conf")]);
+
+    std::fs::remove_file(home.join(".bash_history")).unwrap();
+    assert!(watcher
+        .changes
+        .recv_timeout(Duration::from_millis(600))
+        .is_err());
+}
+
+#[test]
+fn after_a_failure_every_wanted_file_counts() {
+    let dir = scratch_dir("watch-existing");
+    std::fs::write(dir.join("code.json"), "{}").unwrap();
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+    std::fs::write(dir.join(".tmux.conf"), "").unwrap();
+    let set = WatchSet {
+        pack_dirs: vec![dir.clone()],
+        files: vec![dir.join(".tmux.conf"), dir.join("missing/hotkeys.json")],
+        ..WatchSet::default()
+    };
+
+    assert_eq!(
+        set.existing(),
+        [dir.join(".tmux.conf"), dir.join("code.json")]
+    );
+}
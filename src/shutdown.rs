@@ -0,0 +1,140 @@
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use rustix::event::{eventfd, poll, EventfdFlags, PollFd, PollFlags, Timespec};
+use rustix::io::Errno;
+
+#[cfg(test)]
+mod tests;
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+/// Tells the background watchers to return. Clones share the same signal.
+///
+/// Blocking loops either poll [`Shutdown::fd`] next to their own fd, or
+/// register an [`Shutdown::on_stop`] hook that wakes them up.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    stopped: AtomicBool,
+    // becomes readable on stop
+    fd: OwnedFd,
+    // by id, so a child can take its hook back
+    hooks: Mutex<Vec<(u64, Hook)>>,
+    next_hook: AtomicU64,
+    // the parent's hook stopping this one, removed when this is dropped
+    parent: Option<(Weak<Inner>, u64)>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        let Some((parent, id)) = &self.parent else {
+            return;
+        };
+        if let Some(parent) = parent.upgrade() {
+            parent.hooks.lock().unwrap().retain(|(hook, _)| hook != id);
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> anyhow::Result<Self> {
+        Self::with_parent(None)
+    }
+
+    fn with_parent(parent: Option<(Weak<Inner>, u64)>) -> anyhow::Result<Self> {
+        let fd = eventfd(0, EventfdFlags::CLOEXEC | EventfdFlags::NONBLOCK)?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                stopped: AtomicBool::new(false),
+                fd,
+                hooks: Mutex::new(Vec::new()),
+                next_hook: AtomicU64::new(0),
+                parent,
+            }),
+        })
+    }
+
+    /// Signal every watcher. Calling it again is a no-op.
+    pub fn stop(&self) {
+        if self.inner.stopped.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let _ = rustix::io::write(&self.inner.fd, &1u64.to_ne_bytes());
+
+        let hooks = std::mem::take(&mut *self.inner.hooks.lock().unwrap());
+        for (_, hook) in hooks {
+            hook();
+        }
+    }
+
+    /// A signal of its own that also stops when this one does, for a
+    /// watcher that may be replaced while the app keeps running. Dropping
+    /// the child's last clone removes its hook from this one.
+    pub fn child(&self) -> anyhow::Result<Self> {
+        let id = self.inner.next_hook.fetch_add(1, Ordering::Relaxed);
+        let child = Self::with_parent(Some((Arc::downgrade(&self.inner), id)))?;
+        let weak: Weak<Inner> = Arc::downgrade(&child.inner);
+        self.add_hook(id, move || {
+            if let Some(inner) = weak.upgrade() {
+                Shutdown { inner }.stop();
+            }
+        });
+        Ok(child)
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.inner.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Sleep for `timeout`, waking early on stop. Returns whether it stopped.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.is_stopped() {
+                return true;
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+
+            let ts = Timespec::try_from(left).unwrap_or(Timespec {
+                tv_sec: i64::MAX,
+                tv_nsec: 0,
+            });
+            let mut fds = [PollFd::new(&self.inner.fd, PollFlags::IN)];
+            match poll(&mut fds, Some(&ts)) {
+                Ok(_) | Err(Errno::INTR) => {}
+                Err(_) => std::thread::sleep(left),
+            }
+        }
+    }
+
+    /// Readable once [`Shutdown::stop`] has been called.
+    pub fn fd(&self) -> BorrowedFd<'_> {
+        self.inner.fd.as_fd()
+    }
+
+    /// Run `hook` on stop, or right away if already stopped.
+    pub fn on_stop(&self, hook: impl FnOnce() + Send + 'static) {
+        let id = self.inner.next_hook.fetch_add(1, Ordering::Relaxed);
+        self.add_hook(id, hook);
+    }
+
+    fn add_hook(&self, id: u64, hook: impl FnOnce() + Send + 'static) {
+        let mut hooks = self.inner.hooks.lock().unwrap();
+        if self.is_stopped() {
+            drop(hooks);
+            hook();
+        } else {
+            hooks.push((id, Box::new(hook)));
+        }
+    }
+}
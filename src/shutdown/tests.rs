@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use super::Shutdown;
+
+fn hooks(s: &Shutdown) -> usize {
+    s.inner.hooks.lock().unwrap().len()
+}
+
+#[test]
+fn stopping_a_parent_stops_its_children() {
+    let parent = Shutdown::new().unwrap();
+    let child = parent.child().unwrap();
+    let grandchild = child.child().unwrap();
+
+    // not the other way round
+    grandchild.stop();
+    assert!(!child.is_stopped());
+
+    let sibling = parent.child().unwrap();
+    parent.stop();
+    assert!(child.is_stopped());
+    assert!(sibling.is_stopped());
+    assert!(sibling.wait_timeout(Duration::from_secs(5)));
+
+    // a child of a stopped signal starts stopped
+    assert!(parent.child().unwrap().is_stopped());
+}
+
+#[test]
+fn dropped_children_take_their_hook_back() {
+    let parent = Shutdown::new().unwrap();
+
+    // as a subscription restarting its watcher would
+    for _ in 0..100 {
+        let child = parent.child().unwrap();
+        assert!(!child.is_stopped());
+    }
+    assert_eq!(hooks(&parent), 0);
+
+    let child = parent.child().unwrap();
+    let clone = child.clone();
+    drop(child);
+    assert_eq!(hooks(&parent), 1, "a clone still listens");
+    drop(clone);
+    assert_eq!(hooks(&parent), 0);
+
+    // other hooks stay
+    parent.on_stop(|| {});
+    drop(parent.child().unwrap());
+    assert_eq!(hooks(&parent), 1);
+}
+
+#[test]
+fn children_may_outlive_their_parent() {
+    let parent = Shutdown::new().unwrap();
+    let child = parent.child().unwrap();
+    drop(parent);
+
+    assert!(!child.is_stopped());
+    child.stop();
+    assert!(child.wait_timeout(Duration::ZERO));
+}
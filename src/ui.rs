@@ -1,20 +1,36 @@
 use std::collections::BTreeMap;
-use std::fs;
+use std::hash::Hash;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::LazyLock;
 use std::time::Duration;
 
 use cosmic::app::Core;
-use cosmic::iced::{window, Alignment, Fill, Length, Subscription};
+use cosmic::iced::{window, Alignment, Fill, Length, Pixels, Subscription};
 use cosmic::iced::event::{Event, Status};
+use cosmic::iced::futures::SinkExt;
+use cosmic::iced::keyboard::{self, key::Named};
+use cosmic::iced::stream;
 use cosmic::iced::widget::mouse_area;
-use cosmic::widget::{button, column, container, row, scrollable, text, text_input, Space};
+use cosmic::widget::{button, column, container, row, scrollable, text, text_input, Id, Space};
 use cosmic::{Action, Application, Element, Task};
 
-use serde::Deserialize;
 use tokio::sync::mpsc;
 
-use crate::focus_watcher;
-use crate::key_format::pretty_keys;
-use crate::shortcut_resolver::ShortcutResolver;
+use crate::app_keys::{self, AppAction, AppKeys};
+use crate::focus_watcher::{
+    self, Backend, FocusEvent, FocusEventKind, WatcherEvent, WorkspaceEvent,
+};
+use crate::fuzzy::{Fields, Found, Query};
+use crate::key_format::{KeyChord, KeyParseError, KeySequence, Modifiers};
+use crate::key_glyphs::KeyGlyph;
+use crate::key_layout::KeyLayout;
+use crate::shortcut_pack::Layout;
+use crate::shortcut_resolver::{
+    Layer, LoadedPack, MatchRule, ShortcutResolver, DESKTOP_APP_ID,
+};
+use crate::shortcut_watcher::{self, ReloadEvent, WatchSet};
+use crate::shutdown::Shutdown;
 
 // Cleanup function for lock file (safe even if file doesn't exist)
 fn cleanup_lock_file() {
@@ -28,19 +44,106 @@ fn cleanup_lock_file() {
     let _ = std::fs::remove_file(lock_path);
 }
 
-// ---------- JSON ----------
-#[derive(Debug, Clone, Deserialize)]
-struct ShortcutsFile {
-    shortcuts: Vec<ShortcutEntry>,
+/// Columns of the shortcut grid.
+const COLUMNS: usize = 5;
+
+static SEARCH_ID: LazyLock<Id> = LazyLock::new(|| Id::new("search"));
+
+// ---------- Keys ----------
+/// A key press as a chord; see [`app_keys::pressed_chord`].
+fn pressed_chord(key: &keyboard::Key, modifiers: keyboard::Modifiers) -> Option<KeyChord> {
+    let name = match key {
+        keyboard::Key::Named(named) => match named {
+            Named::Escape => "Esc",
+            Named::Enter => "Enter",
+            Named::Tab => "Tab",
+            Named::Space => "Space",
+            Named::Backspace => "Backspace",
+            Named::Delete => "Delete",
+            Named::Home => "Home",
+            Named::End => "End",
+            Named::PageUp => "PageUp",
+            Named::PageDown => "PageDown",
+            Named::ArrowUp => "Up",
+            Named::ArrowDown => "Down",
+            Named::ArrowLeft => "Left",
+            Named::ArrowRight => "Right",
+            Named::F1 => "F1",
+            Named::F2 => "F2",
+            Named::F3 => "F3",
+            Named::F4 => "F4",
+            Named::F5 => "F5",
+            Named::F6 => "F6",
+            Named::F7 => "F7",
+            Named::F8 => "F8",
+            Named::F9 => "F9",
+            Named::F10 => "F10",
+            Named::F11 => "F11",
+            Named::F12 => "F12",
+            _ => return None,
+        }
+        .to_string(),
+        keyboard::Key::Character(c) => c.to_string(),
+        keyboard::Key::Unidentified => return None,
+    };
+    let held = Modifiers {
+        super_key: modifiers.logo(),
+        ctrl: modifiers.control(),
+        alt: modifiers.alt(),
+        shift: modifiers.shift(),
+        ..Modifiers::default()
+    };
+    app_keys::pressed_chord(&name, held)
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct ShortcutEntry {
-    keys: String,
-    #[serde(alias = "description")]
+// ---------- Entries ----------
+/// A loaded entry with its keys already parsed. Bad key strings are kept
+/// (with the error) so they show up flagged instead of as mangled glyphs.
+#[derive(Debug, Clone)]
+struct ShortcutItem {
+    raw_keys: String,
+    keys: Result<KeySequence, KeyParseError>,
     desc: String,
-    #[serde(default)]
-    category: Option<String>,
+    category: String,
+    // set when the entry comes from a higher layer than the base pack
+    provenance: Option<Layer>,
+    // key labels the search matches, shown and as the pack spells them
+    search_keys: Vec<String>,
+    // how the current search matched, `None` when it didn't or is empty
+    found: Option<Found>,
+}
+
+impl ShortcutItem {
+    fn keys_display(&self) -> String {
+        match &self.keys {
+            Ok(seq) => seq.glyphs(),
+            Err(_) => format!("⚠ {}", self.raw_keys),
+        }
+    }
+
+    fn find(&self, query: &Query) -> Option<Found> {
+        query.find(Fields {
+            desc: &self.desc,
+            category: &self.category,
+            keys: &self.search_keys,
+        })
+    }
+}
+
+/// Key labels for the search: those shown, plus the pack's spelling, so
+/// it finds physical labels too.
+fn search_keys(keys: &Result<KeySequence, KeyParseError>, raw_keys: &str) -> Vec<String> {
+    let mut out = match (keys, KeySequence::parse(raw_keys)) {
+        (Ok(shown), Ok(raw)) => [shown.search_tokens(), raw.search_tokens()].concat(),
+        _ => raw_keys
+            .to_lowercase()
+            .split(['+', ' '])
+            .map(str::to_string)
+            .collect(),
+    };
+    out.sort_unstable();
+    out.dedup();
+    out
 }
 
 // ---------- Messages ----------
@@ -48,16 +151,64 @@ struct ShortcutEntry {
 pub enum Message {
     AppIdChanged(String),
     SearchChanged(String),
-    Tick,
+    // from the background watchers
+    FocusBackend(Option<Backend>),
+    FocusChanged(FocusEvent),
+    FocusWatcherDisconnected { error: String, retry_in: Duration },
+    WorkspaceChanged(WorkspaceEvent),
+    KeymapChanged(String),
+    KeyboardGroupChanged(u32),
+    ShortcutFilesChanged(Vec<PathBuf>),
+    ShortcutWatcherFailed { error: String, retry_in: Duration },
+    // `captured` when a widget, i.e. the search field, already used it
+    KeyPressed { chord: KeyChord, captured: bool },
 
     ToggleSettings,
     CloseSettings,
+    TogglePhysicalKeys,
 
     GoHome,
 
     QuitRequested,
 }
 
+/// Stops a watcher when its subscription is dropped.
+struct StopOnDrop(Shutdown);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+/// Run a blocking watcher on the blocking pool for as long as the subscription
+/// is alive, turning whatever it sends into messages as they arrive. The
+/// watcher gets a signal that stops on quit, or when a new `id` replaces it.
+fn watcher_subscription<T: Send + 'static>(
+    id: impl Hash + 'static,
+    shutdown: &Shutdown,
+    run: impl FnOnce(Shutdown, mpsc::UnboundedSender<T>) + Send + 'static,
+    to_message: fn(T) -> Message,
+) -> Subscription<Message> {
+    let shutdown = shutdown.clone();
+    Subscription::run_with_id(
+        id,
+        stream::channel(16, move |mut output| async move {
+            let Ok(stop) = shutdown.child() else { return };
+            let _stop_on_drop = StopOnDrop(stop.clone());
+
+            let (tx, mut rx) = mpsc::unbounded_channel::<T>();
+            tokio::task::spawn_blocking(move || run(stop, tx));
+
+            while let Some(v) = rx.recv().await {
+                if output.send(to_message(v)).await.is_err() {
+                    break;
+                }
+            }
+        }),
+    )
+}
+
 // ---------- App ----------
 pub struct OrbitKeysUi {
     core: Core,
@@ -65,15 +216,52 @@ pub struct OrbitKeysUi {
 
     app_id_text: String,
     search: String,
-
-    // (keys, desc, category)
-    items: Vec<(String, String, String)>,
+    // `search` parsed, kept with it
+    query: Query,
+
+    items: Vec<ShortcutItem>,
+    // the pack behind `items`, kept to re-apply title contexts
+    pack: Option<LoadedPack>,
+    window_title: String,
+    active_contexts: Vec<String>,
+    // of the active workspace, when the compositor reports it
+    layout: Option<Layout>,
     load_error: Option<String>,
+    // files backing `items`, so we know when a reload is needed
+    loaded_files: Vec<PathBuf>,
+    pack_name: Option<String>,
+    // how the app id was matched, when it wasn't an exact alias
+    match_note: Option<String>,
+    category_order: Vec<String>,
+    // validation findings in the loaded files
+    issue_count: usize,
 
-    focus_rx: mpsc::UnboundedReceiver<String>,
     last_target_app_id: Option<String>,
+    // showing the desktop pack because no window has focus
+    desktop_mode: bool,
+    // how focus is tracked; `Some(None)` when the compositor can't tell us
+    focus_backend: Option<Option<Backend>>,
+    // why the focus watcher is reconnecting, until it is back
+    watcher_error: Option<String>,
+    // why the shortcut files aren't watched or re-read, until a reload works
+    reload_error: Option<String>,
+    // what the shortcut watcher observes; a new one restarts it
+    watch_set: WatchSet,
+    // the active keyboard layout, once known
+    key_layout: Option<KeyLayout>,
+    // the keymap's active layout, as the compositor last reported it
+    key_group: u32,
+    // label keys by the keycaps to press on `key_layout`
+    physical_keys: bool,
+    // stops the watcher threads on quit
+    shutdown: Shutdown,
 
     show_settings: bool,
+
+    // the app's own key bindings
+    app_keys: AppKeys,
+    // index into `visible_items`, moved with the arrow keys
+    selected: Option<usize>,
 }
 
 /// Single-line truncation with ellipsis.
@@ -104,35 +292,101 @@ fn no_wrap_spaces(s: &str) -> String {
     s.replace(' ', "\u{00A0}")
 }
 
+/// `desc` on one line, ellipsized, with the chars in `spans` (char indices
+/// into `desc`) in bold accent.
+fn highlighted_desc<'a>(
+    desc: &str,
+    spans: &[Range<usize>],
+    max_chars: usize,
+    size: impl Into<Pixels> + Copy,
+) -> Element<'a, Message> {
+    let one_line = desc.replace('\n', " ");
+    // `ellipsize` trims, shifting indices by the leading whitespace
+    let lead = one_line.chars().take_while(|c| c.is_whitespace()).count();
+    let shown: Vec<char> = ellipsize(&one_line, max_chars).chars().collect();
+    let shown_len = shown.len();
+    // the "…" of a truncated one isn't part of `desc`
+    let truncated = shown_len < one_line.trim().chars().count();
+    let hit = |k: usize| {
+        !(truncated && k + 1 == shown_len) && spans.iter().any(|r| r.contains(&(k + lead)))
+    };
+
+    // runs of chars that are all highlighted or all not
+    let mut segments: Vec<(bool, String)> = Vec::new();
+    for (k, c) in shown.into_iter().enumerate() {
+        match segments.last_mut() {
+            Some((h, seg)) if *h == hit(k) => seg.push(c),
+            _ => segments.push((hit(k), c.to_string())),
+        }
+    }
+
+    let mut out = row().align_y(Alignment::Center);
+    for (h, seg) in segments {
+        let t = text(no_wrap_spaces(&seg)).size(size);
+        out = out.push(match h {
+            true => t
+                .font(cosmic::font::bold())
+                .class(cosmic::theme::Text::Accent),
+            false => t,
+        });
+    }
+    out.into()
+}
+
 impl OrbitKeysUi {
-    fn filtered_items(&self) -> Vec<(String, String, String)> {
-        let q = self.search.trim().to_lowercase();
-        if q.is_empty() {
-            return self.items.clone();
+    /// Items matching the search, best first.
+    fn filtered_items(&self) -> Vec<&ShortcutItem> {
+        if self.query.is_empty() {
+            return self.items.iter().collect();
         }
 
-        self.items
+        let mut found: Vec<(u32, &ShortcutItem)> = self
+            .items
             .iter()
-            .cloned()
-            .filter(|(k, d, c)| {
-                k.to_lowercase().contains(&q)
-                    || d.to_lowercase().contains(&q)
-                    || c.to_lowercase().contains(&q)
-            })
-            .collect()
+            .filter_map(|item| item.found.as_ref().map(|f| (f.score, item)))
+            .collect();
+        // stable, so equal scores keep the pack's order
+        found.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        found.into_iter().map(|(_, item)| item).collect()
     }
 
-    fn grouped_items(&self) -> BTreeMap<String, Vec<(String, String)>> {
-        let mut map: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
-        for (keys, desc, cat) in self.filtered_items() {
-            map.entry(cat).or_default().push((keys, desc));
+    fn grouped_items(&self) -> BTreeMap<String, Vec<&ShortcutItem>> {
+        let mut map: BTreeMap<String, Vec<&ShortcutItem>> = BTreeMap::new();
+        for item in self.filtered_items() {
+            map.entry(item.category.clone()).or_default().push(item);
         }
         map
     }
 
-    fn grouped_columns(&self, max_cols: usize) -> Vec<Vec<(String, Vec<(String, String)>)>> {
-        let grouped = self.grouped_items();
-        let mut cols: Vec<Vec<(String, Vec<(String, String)>)>> =
+    /// Categories in the pack's declared order, then the rest alphabetically.
+    /// While searching, by their best match instead.
+    fn ordered_groups(&self) -> Vec<(String, Vec<&ShortcutItem>)> {
+        if !self.query.is_empty() {
+            let mut out: Vec<(String, Vec<&ShortcutItem>)> = Vec::new();
+            for item in self.filtered_items() {
+                match out.iter_mut().find(|(cat, _)| *cat == item.category) {
+                    Some((_, entries)) => entries.push(item),
+                    None => out.push((item.category.clone(), vec![item])),
+                }
+            }
+            return out;
+        }
+
+        let mut grouped = self.grouped_items();
+        let mut out = Vec::with_capacity(grouped.len());
+
+        for cat in &self.category_order {
+            if let Some(entries) = grouped.remove(cat) {
+                out.push((cat.clone(), entries));
+            }
+        }
+        out.extend(grouped);
+        out
+    }
+
+    fn grouped_columns(&self, max_cols: usize) -> Vec<Vec<(String, Vec<&ShortcutItem>)>> {
+        let grouped = self.ordered_groups();
+        let mut cols: Vec<Vec<(String, Vec<&ShortcutItem>)>> =
             (0..max_cols).map(|_| Vec::new()).collect();
 
         for (i, (category, entries)) in grouped.into_iter().enumerate() {
@@ -142,44 +396,169 @@ impl OrbitKeysUi {
         cols
     }
 
+    /// Items in the order the grid shows them: column by column, each
+    /// category top to bottom.
+    fn visible_items(&self) -> Vec<&ShortcutItem> {
+        self.grouped_columns(COLUMNS)
+            .into_iter()
+            .flatten()
+            .flat_map(|(_, entries)| entries)
+            .collect()
+    }
+
+    /// Move the selection; see [`app_keys::move_selection`].
+    fn move_selection(&mut self, step: isize, by_category: bool) {
+        let items = self.visible_items();
+        let categories: Vec<&str> = items.iter().map(|i| i.category.as_str()).collect();
+        self.selected = app_keys::move_selection(&categories, self.selected, step, by_category);
+    }
+
+    fn on_key_pressed(&mut self, chord: KeyChord, captured: bool) -> Task<Action<Message>> {
+        // the search field keeps what it types
+        if captured && app_keys::edits_text(&chord) {
+            return Task::none();
+        }
+        let Some(action) = self.app_keys.action(&chord) else {
+            return Task::none();
+        };
+
+        match action {
+            AppAction::FocusSearch => return text_input::focus(SEARCH_ID.clone()),
+            AppAction::ClearSearch => {
+                if self.show_settings {
+                    self.show_settings = false;
+                } else if !self.search.is_empty() {
+                    self.set_search(String::new());
+                } else {
+                    self.selected = None;
+                }
+            }
+            AppAction::PreviousEntry => self.move_selection(-1, false),
+            AppAction::NextEntry => self.move_selection(1, false),
+            AppAction::PreviousCategory => self.move_selection(-1, true),
+            AppAction::NextCategory => self.move_selection(1, true),
+            AppAction::Home => self.set_active_app(DESKTOP_APP_ID),
+            AppAction::Settings => self.show_settings = !self.show_settings,
+            AppAction::Quit => return self.update(Message::QuitRequested),
+        }
+        Task::none()
+    }
+
+    fn set_search(&mut self, search: String) {
+        self.query = Query::parse(&search);
+        self.search = search;
+        self.selected = None;
+        self.find_items();
+    }
+
+    /// Match every item against the search, once per change to either.
+    fn find_items(&mut self) {
+        for item in &mut self.items {
+            item.found = match self.query.is_empty() {
+                true => None,
+                false => item.find(&self.query),
+            };
+        }
+    }
+
     fn load_for_app_id(&mut self, app_id: &str) {
         self.items.clear();
+        self.pack = None;
+        self.active_contexts.clear();
         self.load_error = None;
+        self.loaded_files.clear();
+        self.selected = None;
+        self.pack_name = None;
+        self.match_note = None;
+        self.category_order.clear();
+        self.issue_count = 0;
 
         let app_id = app_id.trim();
         if app_id.is_empty() {
             return;
         }
 
-        let Some(path) = self.resolver.resolve(app_id) else {
-            self.load_error = Some(format!("No shortcuts for app_id: {app_id}"));
-            return;
-        };
+        if let Some(r) = self.resolver.resolve_explained(app_id) {
+            self.loaded_files = r.files.iter().map(|f| f.path.clone()).collect();
+            if r.rule != MatchRule::ExactAlias {
+                self.match_note = Some(format!("via {}", r.rule));
+            }
+        }
 
-        let raw = match fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(e) => {
-                self.load_error = Some(e.to_string());
+        let mut pack = match self.resolver.load(app_id) {
+            Ok(Some(pack)) => pack,
+            Ok(None) => {
+                self.load_error = Some(format!("No shortcuts for app_id: {app_id}"));
                 return;
             }
-        };
-
-        let parsed: ShortcutsFile = match serde_json::from_str(&raw) {
-            Ok(v) => v,
             Err(e) => {
                 self.load_error = Some(e.to_string());
                 return;
             }
         };
 
-        self.items = parsed
-            .shortcuts
+        self.pack_name = pack.name.clone();
+        self.category_order = pack.categories.clone();
+        self.issue_count = self.resolver.diagnostics(&pack.files).count();
+        pack.set_hinted(self.resolver.hinted_contexts(&self.window_title));
+        self.pack = Some(pack);
+
+        self.rebuild_items();
+    }
+
+    /// Recompute `items` from the loaded pack and the focused window's title.
+    fn rebuild_items(&mut self) {
+        self.selected = None;
+        let Some(pack) = &self.pack else { return };
+
+        let base_layer = pack.files.first().map(|f| f.layer);
+        self.active_contexts = pack
+            .active_contexts(&self.window_title)
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+
+        let key_layout = self.key_layout.as_ref().filter(|_| self.physical_keys);
+        self.items = pack
+            .entries_for_title(&self.window_title)
             .into_iter()
-            .map(|s| {
-                let cat = s.category.unwrap_or_else(|| "General".into());
-                (s.keys, s.desc, cat)
+            .filter(|e| e.applies_to(self.layout))
+            .map(|e| {
+                let keys = KeySequence::parse(&e.keys).map(|seq| match key_layout {
+                    Some(l) => seq.physical(l),
+                    None => seq,
+                });
+                ShortcutItem {
+                    search_keys: search_keys(&keys, &e.keys),
+                    keys,
+                    raw_keys: e.keys,
+                    desc: e.desc,
+                    category: e.category.unwrap_or_else(|| "General".into()),
+                    provenance: (Some(e.layer) != base_layer).then_some(e.layer),
+                    found: None,
+                }
             })
             .collect();
+        self.find_items();
+    }
+
+    fn set_window_title(&mut self, title: &str) {
+        if self.window_title == title {
+            return;
+        }
+        self.window_title = title.to_string();
+
+        let hinted = self.resolver.hinted_contexts(title);
+        let Some(pack) = &mut self.pack else { return };
+        pack.set_hinted(hinted);
+        let now: Vec<&str> = pack
+            .active_contexts(title)
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        if now != self.active_contexts {
+            self.rebuild_items();
+        }
     }
 
     fn set_active_app(&mut self, app_id: &str) {
@@ -187,7 +566,9 @@ impl OrbitKeysUi {
         if app_id.is_empty() {
             return;
         }
+        self.desktop_mode = false;
 
+        // Focus events are per window; only reload when the app changes.
         if self.last_target_app_id.as_deref() == Some(app_id) {
             return;
         }
@@ -197,28 +578,107 @@ impl OrbitKeysUi {
         self.load_for_app_id(app_id);
     }
 
-    fn drain_focus_updates(&mut self) {
-        let mut latest = None;
-        while let Ok(v) = self.focus_rx.try_recv() {
-            latest = Some(v);
+    fn on_focus_changed(&mut self, ev: FocusEvent) {
+        // Nothing focused (desktop, launcher or workspaces overview, or the
+        // last window closed): show the desktop until a window is activated.
+        if ev.kind == FocusEventKind::FocusLost {
+            self.set_window_title("");
+            self.set_active_app(DESKTOP_APP_ID);
+            self.desktop_mode = true;
+            return;
         }
 
-        let Some(app_id) = latest else { return };
-
         // Ignore our own window
-        if app_id == Self::APP_ID {
+        if ev.app_id == Self::APP_ID {
+            return;
+        }
+
+        if ev.kind == FocusEventKind::Closed {
+            return;
+        }
+
+        // A new title may switch contexts within the same pack.
+        self.set_window_title(&ev.title);
+        if ev.kind != FocusEventKind::Activated {
             return;
         }
 
-        let app_id = app_id.trim().to_string();
+        let app_id = ev.app_id.trim().to_string();
         if app_id.is_empty() || app_id == "unknown" {
-            // Don’t try to infer desktop focus. User uses Home button for root.
+            // Can't tell what this is; keep showing the current pack.
             return;
         }
 
         self.set_active_app(&app_id);
     }
 
+    fn on_workspace_changed(&mut self, ev: WorkspaceEvent) {
+        let layout = ev
+            .tiling
+            .map(|t| if t { Layout::Tiling } else { Layout::Floating });
+        if layout != self.layout {
+            self.layout = layout;
+            self.rebuild_items();
+        }
+    }
+
+    fn on_keymap_changed(&mut self, keymap: &str) {
+        // keep the previous layout if this one doesn't compile
+        if let Some(mut layout) = KeyLayout::from_keymap(keymap) {
+            layout.set_group(self.key_group);
+            self.key_layout = Some(layout);
+            if self.physical_keys {
+                self.rebuild_items();
+            }
+        }
+    }
+
+    fn on_keyboard_group_changed(&mut self, group: u32) {
+        self.key_group = group;
+        if let Some(layout) = &mut self.key_layout {
+            layout.set_group(group);
+            if self.physical_keys {
+                self.rebuild_items();
+            }
+        }
+    }
+
+    fn on_shortcut_files_changed(&mut self, changed: Vec<PathBuf>) {
+        // An app was installed or removed: its desktop file may resolve ids.
+        let is_desktop = |p: &PathBuf| p.extension().is_some_and(|e| e == "desktop");
+        if changed.iter().any(is_desktop) {
+            self.resolver.rescan_desktop();
+        }
+
+        // A file may have been added, removed or had its app_ids edited.
+        if let Err(e) = self.resolver.rebuild_index() {
+            self.reload_error = Some(format!("⚠ shortcut files not reloaded: {e:#}"));
+            return;
+        }
+        self.reload_error = None;
+        // e.g. Obsidian opened another vault
+        self.watch_set = self.resolver.watch_set();
+        self.app_keys = AppKeys::load(&self.resolver);
+
+        let Some(app_id) = self.last_target_app_id.clone() else { return };
+        let resolved: Vec<PathBuf> = self
+            .resolver
+            .resolve(&app_id)
+            .map(|files| files.iter().map(|f| f.path.clone()).collect())
+            .unwrap_or_default();
+
+        let backing_changed = self.loaded_files.iter().any(|p| changed.contains(p));
+        // nested packs aren't tracked file by file; reloading is cheap
+        let nests = self
+            .pack
+            .as_ref()
+            .is_some_and(|p| p.contexts.iter().any(|c| c.pack.is_some()));
+
+        if backing_changed || nests || resolved != self.loaded_files {
+            self.load_for_app_id(&app_id);
+        }
+    }
+
     fn overlay_controls(&self) -> Element<'_, Message> {
         // bottom-right icon-only controls (Home + Gear)
         container(
@@ -251,8 +711,6 @@ impl OrbitKeysUi {
     }
 
     fn legend_overlay(&self) -> Element<'_, Message> {
-        use crate::key_glyphs::KeyGlyph;
-
         let mut legend_row = row()
             .spacing(14)
             .align_y(Alignment::Center);
@@ -304,6 +762,7 @@ impl OrbitKeysUi {
             .spacing(12)
             .push(text("Settings").size(20))
             .push(Space::with_height(Length::Fixed(6.0)))
+            .push(self.key_labels_setting())
             .push(
                 row()
                     .width(Length::Fill)
@@ -344,13 +803,32 @@ impl OrbitKeysUi {
 
         cosmic::iced::widget::stack![blocker, centered].into()
     }
+
+    /// Logical labels are the pack's; physical ones the keycaps to press.
+    fn key_labels_setting(&self) -> Element<'_, Message> {
+        let label = match (&self.key_layout, self.physical_keys) {
+            (None, _) => "Logical (layout unknown)".to_string(),
+            (Some(_), false) => "Logical".to_string(),
+            (Some(layout), true) => format!("Physical ({})", layout.name()),
+        };
+        let toggle = button::text(label)
+            .on_press_maybe(self.key_layout.is_some().then_some(Message::TogglePhysicalKeys));
+
+        row()
+            .width(Length::Fill)
+            .align_y(Alignment::Center)
+            .push(text("Key labels").size(16))
+            .push(Space::with_width(Length::Fill))
+            .push(toggle)
+            .into()
+    }
 }
 
 impl Application for OrbitKeysUi {
     const APP_ID: &'static str = "xyz.fonzi.orbitkeys";
 
     type Executor = cosmic::executor::Default;
-    type Flags = ShortcutResolver;
+    type Flags = (ShortcutResolver, Shutdown);
     type Message = Message;
 
     fn core(&self) -> &Core {
@@ -361,14 +839,10 @@ impl Application for OrbitKeysUi {
         &mut self.core
     }
 
-    fn init(core: Core, resolver: Self::Flags) -> (Self, Task<Action<Self::Message>>) {
-        let (tx, rx) = mpsc::unbounded_channel::<String>();
-
-        tokio::task::spawn_blocking(move || {
-            let _ = focus_watcher::run_focus_watcher(move |app_id| {
-                let _ = tx.send(app_id);
-            });
-        });
+    fn init(core: Core, flags: Self::Flags) -> (Self, Task<Action<Self::Message>>) {
+        let (resolver, shutdown) = flags;
+        let app_keys = AppKeys::load(&resolver);
+        let watch_set = resolver.watch_set();
 
         (
             Self {
@@ -376,11 +850,31 @@ impl Application for OrbitKeysUi {
                 resolver,
                 app_id_text: String::new(),
                 search: String::new(),
+                query: Query::default(),
                 items: Vec::new(),
+                pack: None,
+                window_title: String::new(),
+                active_contexts: Vec::new(),
+                layout: None,
                 load_error: None,
-                focus_rx: rx,
+                loaded_files: Vec::new(),
+                pack_name: None,
+                match_note: None,
+                category_order: Vec::new(),
+                issue_count: 0,
                 last_target_app_id: None,
+                desktop_mode: false,
+                focus_backend: None,
+                watcher_error: None,
+                reload_error: None,
+                watch_set,
+                key_layout: KeyLayout::from_cosmic_config(),
+                key_group: 0,
+                physical_keys: false,
+                shutdown,
                 show_settings: false,
+                app_keys,
+                selected: None,
             },
             Task::none(),
         )
@@ -392,20 +886,53 @@ impl Application for OrbitKeysUi {
                 self.app_id_text = v;
                 let id = self.app_id_text.trim().to_string();
                 if !id.is_empty() {
+                    // A typed id isn't the focused window, so neither its
+                    // title contexts nor its title hints apply.
+                    self.set_window_title("");
                     self.set_active_app(&id);
                 }
             }
-            Message::SearchChanged(v) => self.search = v,
-            Message::Tick => self.drain_focus_updates(),
+            Message::SearchChanged(v) => self.set_search(v),
+            Message::FocusBackend(backend) => {
+                self.focus_backend = Some(backend);
+                self.watcher_error = None;
+            }
+            Message::FocusChanged(ev) => self.on_focus_changed(ev),
+            Message::FocusWatcherDisconnected { error, retry_in } => {
+                self.watcher_error = Some(format!(
+                    "⚠ focus tracking lost: {error} (retrying in {}s)",
+                    retry_in.as_secs()
+                ));
+            }
+            Message::WorkspaceChanged(ev) => self.on_workspace_changed(ev),
+            Message::KeymapChanged(keymap) => self.on_keymap_changed(&keymap),
+            Message::KeyboardGroupChanged(group) => self.on_keyboard_group_changed(group),
+            Message::ShortcutFilesChanged(paths) => self.on_shortcut_files_changed(paths),
+            Message::ShortcutWatcherFailed { error, retry_in } => {
+                // cleared by the reload that follows once watching works again
+                self.reload_error = Some(format!(
+                    "⚠ shortcut files not watched: {error} (retrying in {}s)",
+                    retry_in.as_secs()
+                ));
+            }
+            Message::KeyPressed { chord, captured } => {
+                return self.on_key_pressed(chord, captured);
+            }
 
             Message::ToggleSettings => self.show_settings = !self.show_settings,
             Message::CloseSettings => self.show_settings = false,
+            Message::TogglePhysicalKeys => {
+                self.physical_keys = !self.physical_keys;
+                self.rebuild_items();
+            }
 
-            Message::GoHome => self.set_active_app("root"),
+            Message::GoHome => self.set_active_app(DESKTOP_APP_ID),
 
             Message::QuitRequested => {
+                // The runtime waits for the blocking watcher tasks on exit.
+                self.shutdown.stop();
                 cleanup_lock_file();
-                std::process::exit(0);
+                return cosmic::iced::exit();
             }
         }
 
@@ -421,13 +948,58 @@ impl Application for OrbitKeysUi {
         })
         .map(|(_, msg)| msg);
 
-        let tick = cosmic::iced::time::every(Duration::from_millis(90)).map(|_| Message::Tick);
+        let keys = cosmic::iced::event::listen_with(|event, status, _| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                pressed_chord(&key, modifiers).map(|chord| Message::KeyPressed {
+                    chord,
+                    captured: status == Status::Captured,
+                })
+            }
+            _ => None,
+        });
 
-        Subscription::batch(vec![close, tick])
+        let focus = watcher_subscription(
+            "focus-watcher",
+            &self.shutdown,
+            move |stop, tx| {
+                focus_watcher::run_focus_watcher(stop, move |ev| {
+                    let _ = tx.send(ev);
+                });
+            },
+            |ev| match ev {
+                WatcherEvent::Backend(backend) => Message::FocusBackend(backend),
+                WatcherEvent::Focus(ev) => Message::FocusChanged(ev),
+                WatcherEvent::Workspace(ev) => Message::WorkspaceChanged(ev),
+                WatcherEvent::Keymap(keymap) => Message::KeymapChanged(keymap),
+                WatcherEvent::KeyboardGroup(group) => Message::KeyboardGroupChanged(group),
+                WatcherEvent::Disconnected { error, retry_in } => {
+                    Message::FocusWatcherDisconnected { error, retry_in }
+                }
+            },
+        );
+
+        let watch_set = self.watch_set.clone();
+        let reload = watcher_subscription(
+            ("shortcut-watcher", self.watch_set.clone()),
+            &self.shutdown,
+            move |stop, tx| {
+                shortcut_watcher::run_shortcut_watcher(watch_set, stop, move |ev| {
+                    let _ = tx.send(ev);
+                });
+            },
+            |ev| match ev {
+                ReloadEvent::Changed(paths) => Message::ShortcutFilesChanged(paths),
+                ReloadEvent::Failed { error, retry_in } => {
+                    Message::ShortcutWatcherFailed { error, retry_in }
+                }
+            },
+        );
+
+        Subscription::batch(vec![close, keys, focus, reload])
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        let header = row()
+        let mut header = row()
             .spacing(10)
             .align_y(Alignment::Center)
             .push(text("OrbitKeys").size(26))
@@ -439,18 +1011,70 @@ impl Application for OrbitKeysUi {
                     .width(220),
             );
 
+        if let Some(name) = &self.pack_name {
+            header = header.push(text(name.as_str()).size(13));
+        }
+        if let Some(note) = &self.match_note {
+            header = header.push(text(note.as_str()).size(11));
+        }
+        if self.desktop_mode {
+            header = header.push(text("· no window focused").size(12));
+        }
+        if let Some(err) = &self.watcher_error {
+            header = header.push(text(err.as_str()).size(12));
+        }
+        if let Some(err) = &self.reload_error {
+            header = header.push(text(err.as_str()).size(12));
+        }
+        match self.focus_backend {
+            Some(None) => header = header.push(text("⚠ focus tracking unavailable").size(12)),
+            Some(Some(backend)) if !backend.tracks_focus() => {
+                let note = format!("· focus guessed ({})", backend.label());
+                header = header.push(text(note).size(12));
+            }
+            _ => {}
+        }
+        let layout_specific = self
+            .pack
+            .as_ref()
+            .is_some_and(|p| p.entries.iter().any(|e| e.layout.is_some()));
+        if let Some(layout) = self.layout.filter(|_| layout_specific) {
+            let label = match layout {
+                Layout::Tiling => "· tiling workspace",
+                Layout::Floating => "· floating workspace",
+            };
+            header = header.push(text(label).size(12));
+        }
+        if !self.active_contexts.is_empty() {
+            header = header.push(text(format!("· {}", self.active_contexts.join(", "))).size(12));
+        }
+        if let Some(layout) = self.key_layout.as_ref().filter(|_| self.physical_keys) {
+            header = header.push(text(format!("· keys for {}", layout.name())).size(12));
+        }
+        if self.issue_count > 0 {
+            header = header.push(text(format!("⚠ {} pack issues", self.issue_count)).size(12));
+        }
+
         let search_row = row()
             .spacing(10)
             .align_y(Alignment::Center)
             .push(text("Search:").size(13))
             .push(
                 text_input("type to filter…", &self.search)
+                    .id(SEARCH_ID.clone())
                     .on_input(Message::SearchChanged)
                     .width(Fill),
             );
 
         let main_body: Element<'_, Message> = if self.items.is_empty() && self.load_error.is_none() {
-            container(text("Focus an app to load shortcuts.").size(14))
+            let hint = if self.focus_backend == Some(None) {
+                "This compositor doesn't report the focused window \
+                 (no COSMIC, wlr or ext-foreign-toplevel protocol). \
+                 Type an app id above to load its shortcuts."
+            } else {
+                "Focus an app to load shortcuts."
+            };
+            container(text(hint).size(14))
                 .padding(16)
                 .width(Fill)
                 .height(Fill)
@@ -462,7 +1086,8 @@ impl Application for OrbitKeysUi {
                 .height(Fill)
                 .into()
         } else {
-            let cols = self.grouped_columns(5);
+            let cols = self.grouped_columns(COLUMNS);
+            let selected = self.selected.and_then(|i| self.visible_items().get(i).copied());
 
             let key_size = 16;
             let desc_size = 12;
@@ -477,18 +1102,27 @@ impl Application for OrbitKeysUi {
                 for (category, entries) in col {
                     let mut cat_block = column().spacing(entry_gap).push(text(category).size(18));
 
-                    for (keys, desc) in entries {
-                        let keys_pretty = pretty_keys(&keys);
+                    for item in entries {
+                        let keys_pretty = item.keys_display();
+                        let spans = item.found.as_ref().map_or(&[][..], |f| &f.desc_spans);
                         let desc_one =
-                            no_wrap_spaces(&ellipsize(&desc.replace('\n', " "), desc_max_chars));
+                            highlighted_desc(&item.desc, spans, desc_max_chars, desc_size);
 
-                        let entry = row()
+                        let mut entry = row()
                             .spacing(8)
                             .align_y(Alignment::Center)
                             .push(text(keys_pretty).size(key_size))
-                            .push(text(desc_one).size(desc_size));
+                            .push(desc_one);
+
+                        if let Some(layer) = item.provenance {
+                            entry = entry.push(text(layer.label()).size(10));
+                        }
 
-                        cat_block = cat_block.push(entry);
+                        let is_selected = selected.is_some_and(|s| std::ptr::eq(s, item));
+                        cat_block = cat_block.push(match is_selected {
+                            true => container(entry).class(cosmic::theme::Container::Card),
+                            false => container(entry),
+                        });
                     }
 
                     col_widget = col_widget.push(container(cat_block).padding(6));